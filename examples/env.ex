@@ -0,0 +1,15 @@
+write("script called with ", args.len(), " args")
+writeln()
+if args.len() > 0 {
+    write("first arg is ", args.get(0))
+    writeln()
+}
+write("HOME is ", env("HOME", "not set"))
+writeln()
+set_env("EX_EXAMPLE", "value")
+write("EX_EXAMPLE is ", env("EX_EXAMPLE"))
+writeln()
+unset_env("EX_EXAMPLE")
+write("EX_EXAMPLE after unset is ", env("EX_EXAMPLE", "not set"))
+writeln()
+exit(3)
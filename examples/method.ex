@@ -7,4 +7,9 @@ writeln()
 write("2 ^ 8 == ", 2.pow(4).pow(2))
 writeln()
 
- 
\ No newline at end of file
+ 
+xs = [1, 2]
+ys = xs
+xs.push(3)
+write("pushed: ", xs, ", not changed: ", ys, ", popped: ", xs.pop())
+writeln()
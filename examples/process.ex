@@ -0,0 +1,14 @@
+p = spawn("sh", ["-c", "cat; echo; echo done"])
+write("started process ", p.pid())
+writeln()
+p.write_stdin("hello from ex")
+p.close_stdin()
+write("output: ", p.stdout_lines())
+writeln()
+write("exit code: ", p.wait())
+writeln()
+
+server = spawn("sleep", ["100"], {"timeout": 5})
+server.kill("TERM")
+write("sleep exit code: ", server.wait())
+writeln()
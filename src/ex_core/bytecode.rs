@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use super::{Token, ValueVariant};
+
+/// Variable slot, that is resolved by the compiler:
+/// local variable of the function or global variable of the interpreter
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Var {
+    Local(usize),
+    Global(usize),
+}
+
+/// Binary operator of the instruction, operators are displayed as tokens
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryOp {
+    Plus,
+    Minus,
+    Multi,
+    Devide,
+    Percent,
+    Pow,
+    Eq,
+    NotEq,
+    More,
+    MoreEq,
+    Less,
+    LessEq,
+}
+
+impl BinaryOp {
+    pub fn from_token(token: &Token) -> Option<BinaryOp> {
+        match token {
+            Token::Plus =>      Some(BinaryOp::Plus),
+            Token::Minus =>     Some(BinaryOp::Minus),
+            Token::Multi =>     Some(BinaryOp::Multi),
+            Token::Devide =>    Some(BinaryOp::Devide),
+            Token::Percent =>   Some(BinaryOp::Percent),
+            Token::Pow =>       Some(BinaryOp::Pow),
+            Token::Eq =>        Some(BinaryOp::Eq),
+            Token::NotEq =>     Some(BinaryOp::NotEq),
+            Token::More =>      Some(BinaryOp::More),
+            Token::MoreEq =>    Some(BinaryOp::MoreEq),
+            Token::Less =>      Some(BinaryOp::Less),
+            Token::LessEq =>    Some(BinaryOp::LessEq),
+            _ =>                None
+        }
+    }
+
+    pub fn token(self) -> Token {
+        match self {
+            BinaryOp::Plus =>       Token::Plus,
+            BinaryOp::Minus =>      Token::Minus,
+            BinaryOp::Multi =>      Token::Multi,
+            BinaryOp::Devide =>     Token::Devide,
+            BinaryOp::Percent =>    Token::Percent,
+            BinaryOp::Pow =>        Token::Pow,
+            BinaryOp::Eq =>         Token::Eq,
+            BinaryOp::NotEq =>      Token::NotEq,
+            BinaryOp::More =>       Token::More,
+            BinaryOp::MoreEq =>     Token::MoreEq,
+            BinaryOp::Less =>       Token::Less,
+            BinaryOp::LessEq =>     Token::LessEq,
+        }
+    }
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.token())
+    }
+}
+
+/// Instruction of the stack VM. Operands are indexes in the tables of the chunk,
+/// jump operands are positions of instructions in the chunk code
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Instruction {
+    /// pushes the constant
+    Constant(usize),
+    Pop,
+    Dup,
+    /// moves the value, that is under 'n' values, to the top of the stack
+    Rotate(usize),
+    Load(Var),
+    Store(Var),
+    /// pushes the item of the variable by 'n' keys from the top of the stack, keys are kept
+    LoadPath(Var, usize),
+    /// pops the value and 'n' keys under it and sets the item of the variable
+    StorePath(Var, usize),
+    BuildList(usize),
+    /// pops values of the map literal, operand is the index of its keys in 'key_lists'
+    BuildMap(usize),
+    Negate,
+    Positive,
+    Binary(BinaryOp),
+    /// compares two values, used by value patterns of match
+    Equal,
+    /// pops value, low and high bounds and pushes true if the value is in the range
+    InRange { inclusive: bool },
+    Index,
+    /// operand is the index of the field name in 'names'
+    Field(usize),
+    Jump(usize),
+    /// pops the bool condition and jumps if it's false
+    JumpIfFalse(usize),
+    JumpIfTrue(usize),
+    /// jumps if the top value is not nil, otherwise pops it
+    JumpIfNotNil(usize),
+    /// jumps if the variable has a value, used to skip default values of given arguments
+    JumpIfAssigned(Var, usize),
+    /// checks the bounds of the for loop on the stack and assigns the lower bound to the variable
+    ForInit(Var),
+    /// jumps if the counter of the for loop reached the upper bound, bounds are dropped
+    ForCheck(usize),
+    /// increments the counter of the for loop and assigns it to the variable
+    ForStep(Var),
+    /// pops 'values' values and pushes 'targets' values for the targets of the multiple assignment,
+    /// the value of the first target is on the top
+    Unpack { values: usize, targets: usize },
+    /// pops the list and pushes its 'n' items, the first item is on the top
+    UnpackList(usize),
+    /// pops the map and pushes its items by keys from 'key_lists', the first item is on the top
+    UnpackMap(usize),
+    /// fails, when no arm of the match matches the value on the top
+    NoMatch,
+    /// calls the std or user function with the name from 'names' and 'argc' arguments on the stack
+    Call { name: usize, argc: usize },
+    /// calls the user function with 'argc' positional arguments, followed by values
+    /// of keyword arguments, which names are in 'key_lists'
+    CallWithKeywords { name: usize, argc: usize, keywords: usize },
+    /// calls the function like 'Call' or 'CallWithKeywords', if 'keywords' is set,
+    /// but the user function reuses the frame of the current function
+    TailCall { name: usize, argc: usize, keywords: Option<usize> },
+    /// calls the method on the value under the arguments
+    CallMethod { name: usize, argc: usize },
+    /// calls the method on the variable, or the function of the module, if it's the module namespace
+    CallNamed { var: Var, name: usize, argc: usize },
+    DefineFunction(usize),
+    /// imports the module with the path and the namespace from 'names'
+    Import { path: usize, namespace: usize },
+    /// verifies, that the statement left on the stack of the frame only 'n' values of enclosing loops
+    CheckStack(usize),
+    /// returns the value on the top from the function
+    Return,
+    /// stops the top level code
+    End,
+}
+
+/// Compiled code with tables of its constants and names
+#[derive(Default, Debug)]
+pub struct Chunk {
+    pub code: Vec<Instruction>,
+    /// lines of calls by positions of their instructions, 0 for other instructions
+    pub lines: Vec<usize>,
+    pub constants: Vec<ValueVariant>,
+    pub names: Vec<String>,
+    pub key_lists: Vec<Vec<String>>,
+    pub functions: Vec<Rc<Function>>,
+    /// names of local variables by their slots, empty for the top level code
+    pub local_names: Vec<String>,
+}
+
+impl Chunk {
+    pub fn add_constant(&mut self, value: ValueVariant) -> usize {
+        if let Some(i) = self.constants.iter().position(|constant| *constant == value) {
+            return i;
+        }
+        self.constants.push(value);
+        self.constants.len() - 1
+    }
+
+    pub fn add_name(&mut self, name: &str) -> usize {
+        if let Some(i) = self.names.iter().position(|n| n == name) {
+            return i;
+        }
+        self.names.push(name.to_string());
+        self.names.len() - 1
+    }
+}
+
+/// Arguments of the user function: the last arguments may have default values,
+/// the variadic argument gets the list of extra positional arguments
+#[derive(Clone, Debug, PartialEq)]
+pub struct Signature {
+    pub name: String,
+    pub args: Vec<String>,
+    /// number of arguments without default values
+    pub required: usize,
+    pub rest: Option<String>,
+}
+
+fn arguments(n: usize) -> String {
+    if n == 1 { String::from("1 argument") } else { format!("{} arguments", n) }
+}
+
+impl Signature {
+    /// expected number of arguments, like '2 arguments' or '1 to 2 arguments'
+    fn arity(&self) -> String {
+        if self.rest.is_some() {
+            format!("at least {}", arguments(self.required))
+        } else if self.required == self.args.len() {
+            arguments(self.required)
+        } else {
+            format!("{} to {}", self.required, arguments(self.args.len()))
+        }
+    }
+
+    /// checks, that the function can be called with 'argc' positional arguments
+    /// and keyword arguments with names 'keywords'
+    pub fn check_args(&self, argc: usize, keywords: &[String]) -> Result<(), String> {
+        let too_many = argc > self.args.len() && self.rest.is_none();
+        if too_many || (argc < self.required && keywords.is_empty()) {
+            return Err(format!("function {} expects {}, got {}", self.name, self.arity(), argc));
+        }
+        for (i, keyword) in keywords.iter().enumerate() {
+            match self.args.iter().position(|arg| arg == keyword) {
+                Some(position) if position < argc || keywords[..i].contains(keyword) => {
+                    return Err(format!("argument '{}' of function {} is given twice", keyword, self.name));
+                }
+                Some(_) => {}
+                None => return Err(format!("function {} has no argument '{}'", self.name, keyword)),
+            }
+        }
+        match self.args[..self.required].iter().skip(argc).find(|arg| !keywords.contains(arg)) {
+            Some(arg) => Err(format!("missing argument '{}' of function {}", arg, self.name)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// signature like 'f(a, [b], ...rest)', arguments with default values are in square brackets
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut args: Vec<String> = self.args.iter().enumerate()
+            .map(|(i, arg)| if i < self.required { arg.clone() } else { format!("[{}]", arg) })
+            .collect();
+        if let Some(rest) = &self.rest {
+            args.push(format!("...{}", rest));
+        }
+        write!(f, "{}({})", self.name, args.join(", "))
+    }
+}
+
+/// Compiled user function
+#[derive(Debug)]
+pub struct Function {
+    pub signature: Signature,
+    pub chunk: Rc<Chunk>,
+}
+
+/// Slots of variables by their names
+#[derive(Clone, Default, Debug)]
+pub struct SlotTable {
+    names: Vec<String>,
+    slots: HashMap<String, usize>,
+}
+
+impl SlotTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// returns the slot of the variable, new variables get the next slot
+    pub fn slot(&mut self, name: &str) -> usize {
+        if let Some(slot) = self.slots.get(name) {
+            return *slot;
+        }
+        self.names.push(name.to_string());
+        self.slots.insert(name.to_string(), self.names.len() - 1);
+        self.names.len() - 1
+    }
+
+    pub fn get(&self, name: &str) -> Option<usize> {
+        self.slots.get(name).copied()
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+}
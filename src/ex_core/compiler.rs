@@ -0,0 +1,650 @@
+use std::mem;
+use std::rc::Rc;
+
+use super::expressions::*;
+use super::bytecode::{BinaryOp, Chunk, Function, Instruction, Signature, SlotTable, Var};
+use super::{Token, ValueVariant};
+
+/// Compiles the syntax tree to the bytecode of the stack VM.
+/// Variables are resolved to slots at compile time: variables of functions are local,
+/// variables of the top level code are global and their slots are shared between compilations
+pub struct Compiler<'a> {
+    chunk: Chunk,
+    /// slots of local variables, None for the top level code
+    locals: Option<SlotTable>,
+    /// name of the compiled function, None for the top level code
+    function: Option<String>,
+    globals: &'a mut SlotTable,
+    /// whether the last compiled expression left its value on the stack, statements leave nothing
+    value_pushed: bool,
+    /// whether the value of the last statement of the program is returned from the chunk
+    keep_result: bool,
+    /// whether the balance of the values stack is checked after every statement
+    check_stack: bool,
+    /// number of values, that enclosing loops keep on the stack
+    stack_depth: usize,
+}
+
+impl<'a> Compiler<'a> {
+    /// compiles the top level code, if 'keep_result' is true and the last statement
+    /// is an expression, the chunk returns its value. If 'check_stack' is true,
+    /// the leak of values on the stack after any statement is an error
+    pub fn compile(program: &dyn Expression, globals: &'a mut SlotTable, keep_result: bool, check_stack: bool) -> Result<Chunk, String> {
+        let mut compiler = Compiler {
+            chunk: Chunk::default(),
+            locals: None,
+            function: None,
+            globals,
+            value_pushed: false,
+            keep_result,
+            check_stack,
+            stack_depth: 0,
+        };
+        program.accept(&mut compiler)?;
+        if compiler.value_pushed && keep_result {
+            compiler.emit(Instruction::Return);
+        } else {
+            compiler.emit(Instruction::End);
+        }
+        Ok(compiler.chunk)
+    }
+
+    fn emit(&mut self, instruction: Instruction) -> usize {
+        self.emit_call(instruction, 0)
+    }
+
+    /// emits the call instruction with the line of the call
+    fn emit_call(&mut self, instruction: Instruction, line: usize) -> usize {
+        self.chunk.code.push(instruction);
+        self.chunk.lines.push(line);
+        self.chunk.code.len() - 1
+    }
+
+    /// sets the target of the jump at 'at' to the next instruction
+    fn patch(&mut self, at: usize) {
+        let target = self.chunk.code.len();
+        self.chunk.code[at] = match self.chunk.code[at] {
+            Instruction::Jump(_) => Instruction::Jump(target),
+            Instruction::JumpIfFalse(_) => Instruction::JumpIfFalse(target),
+            Instruction::JumpIfTrue(_) => Instruction::JumpIfTrue(target),
+            Instruction::JumpIfNotNil(_) => Instruction::JumpIfNotNil(target),
+            Instruction::JumpIfAssigned(var, _) => Instruction::JumpIfAssigned(var, target),
+            Instruction::ForCheck(_) => Instruction::ForCheck(target),
+            other => other,
+        };
+    }
+
+    fn constant(&mut self, value: ValueVariant) {
+        let i = self.chunk.add_constant(value);
+        self.emit(Instruction::Constant(i));
+        self.value_pushed = true;
+    }
+
+    fn var(&mut self, name: &str) -> Var {
+        match self.locals.as_mut() {
+            Some(locals) => Var::Local(locals.slot(name)),
+            None => Var::Global(self.globals.slot(name)),
+        }
+    }
+
+    /// compiles the expression, that must leave its value on the stack
+    fn value(&mut self, expr: &dyn Expression) -> ExpressionVisitResult {
+        expr.accept(self)?;
+        if !self.value_pushed {
+            return Err(String::from("statement can't be used as a value"));
+        }
+        Ok(())
+    }
+
+    /// compiles the statement, its value is dropped
+    fn statement(&mut self, statement: &dyn Expression) -> ExpressionVisitResult {
+        statement.accept(self)?;
+        if self.value_pushed {
+            self.emit(Instruction::Pop);
+        }
+        if self.check_stack {
+            self.emit(Instruction::CheckStack(self.stack_depth));
+        }
+        self.value_pushed = false;
+        Ok(())
+    }
+
+    fn block(&mut self, body: &[Box<dyn Expression>]) -> ExpressionVisitResult {
+        for statement in body.iter() {
+            self.statement(statement.as_ref())?;
+        }
+        Ok(())
+    }
+
+    /// compiles indexes of the assignment target,
+    /// returns the variable and the number of keys of the assigned item inside it
+    fn target_path(&mut self, target: &AssignmentTarget) -> Result<(Var, usize), String> {
+        match target {
+            AssignmentTarget::Name(name) => Ok((self.var(name), 0)),
+            AssignmentTarget::Index(object, index) => {
+                let (var, n) = self.target_path(object)?;
+                self.value(index.as_ref())?;
+                Ok((var, n + 1))
+            }
+            AssignmentTarget::Field(object, field) => {
+                let (var, n) = self.target_path(object)?;
+                self.constant(ValueVariant::String(field.as_str().into()));
+                Ok((var, n + 1))
+            }
+            AssignmentTarget::List(_) | AssignmentTarget::Map(_) => Err(String::from("destructuring pattern can't be indexed")),
+        }
+    }
+
+    /// assigns the value on the top of the stack to the target
+    fn assign_to_target(&mut self, target: &AssignmentTarget) -> ExpressionVisitResult {
+        match target {
+            AssignmentTarget::Name(name) => {
+                let var = self.var(name);
+                self.emit(Instruction::Store(var));
+            }
+            AssignmentTarget::List(targets) => {
+                self.emit(Instruction::UnpackList(targets.len()));
+                for target in targets.iter() {
+                    self.assign_to_target(target)?;
+                }
+            }
+            AssignmentTarget::Map(names) => {
+                self.chunk.key_lists.push(names.clone());
+                self.emit(Instruction::UnpackMap(self.chunk.key_lists.len() - 1));
+                for name in names.iter() {
+                    let var = self.var(name);
+                    self.emit(Instruction::Store(var));
+                }
+            }
+            AssignmentTarget::Index(..) | AssignmentTarget::Field(..) => {
+                let (var, n) = self.target_path(target)?;
+                self.emit(Instruction::Rotate(n));
+                self.emit(Instruction::StorePath(var, n));
+            }
+        }
+        Ok(())
+    }
+
+    fn binary_op(op: &Token) -> Result<BinaryOp, String> {
+        BinaryOp::from_token(op).ok_or(format!("binary op {} not supported", op))
+    }
+
+    /// replaces the call of the compiled function, that is the last instruction, with the tail call,
+    /// so self-recursive functions run in the same frame
+    fn tail_call(&mut self) {
+        let Some(function) = &self.function else {
+            return;
+        };
+        let Some(last) = self.chunk.code.last_mut() else {
+            return;
+        };
+        let (name, argc, keywords) = match *last {
+            Instruction::Call { name, argc } => (name, argc, None),
+            Instruction::CallWithKeywords { name, argc, keywords } => (name, argc, Some(keywords)),
+            _ => return,
+        };
+        if self.chunk.names[name] == *function {
+            *last = Instruction::TailCall { name, argc, keywords };
+        }
+    }
+
+    fn call_args(&mut self, args: &[Box<dyn Expression>]) -> ExpressionVisitResult {
+        for arg in args.iter() {
+            self.value(arg.as_ref())?;
+        }
+        Ok(())
+    }
+}
+
+impl ExpressionVisitor for Compiler<'_> {
+    fn visit_float_literal_expression(&mut self, expr: &FloatLiteralExpression) -> ExpressionVisitResult {
+        self.constant(ValueVariant::Float(expr.f));
+        Ok(())
+    }
+
+    fn visit_int_literal_expression(&mut self, expr: &IntLiteralExpression) -> ExpressionVisitResult {
+        self.constant(ValueVariant::Integer(expr.i));
+        Ok(())
+    }
+
+    fn visit_string_literal_expression(&mut self, expr: &StringLiteralExpression) -> ExpressionVisitResult {
+        self.constant(ValueVariant::String(expr.s.as_str().into()));
+        Ok(())
+    }
+
+    fn visit_bool_literal_expression(&mut self, expr: &BoolLiteralExpression) -> ExpressionVisitResult {
+        self.constant(ValueVariant::Bool(expr.b));
+        Ok(())
+    }
+
+    fn visit_nil_literal_expression(&mut self, _expr: &NilLiteralExpression) -> ExpressionVisitResult {
+        self.constant(ValueVariant::Nil);
+        Ok(())
+    }
+
+    fn visit_list_expression(&mut self, expr: &ListExpression) -> ExpressionVisitResult {
+        for item in expr.items.iter() {
+            self.value(item.as_ref())?;
+        }
+        self.emit(Instruction::BuildList(expr.items.len()));
+        self.value_pushed = true;
+        Ok(())
+    }
+
+    fn visit_map_expression(&mut self, expr: &MapExpression) -> ExpressionVisitResult {
+        let mut keys = Vec::with_capacity(expr.items.len());
+        for (key, item) in expr.items.iter() {
+            self.value(item.as_ref())?;
+            keys.push(key.clone());
+        }
+        self.chunk.key_lists.push(keys);
+        self.emit(Instruction::BuildMap(self.chunk.key_lists.len() - 1));
+        self.value_pushed = true;
+        Ok(())
+    }
+
+    fn visit_name_expression(&mut self, expr: &NameExpression) -> ExpressionVisitResult {
+        let var = self.var(&expr.name);
+        self.emit(Instruction::Load(var));
+        self.value_pushed = true;
+        Ok(())
+    }
+
+    fn visit_unary_expression(&mut self, expr: &UnaryExpression) -> ExpressionVisitResult {
+        self.value(expr.expr.as_ref())?;
+        match expr.op {
+            Token::Minus => self.emit(Instruction::Negate),
+            Token::Plus => self.emit(Instruction::Positive),
+            _ => return Err(format!("unsupported unary op {}", expr.op)),
+        };
+        self.value_pushed = true;
+        Ok(())
+    }
+
+    fn visit_binary_expression(&mut self, expr: &BinaryExpression) -> ExpressionVisitResult {
+        self.value(expr.left.as_ref())?;
+        // right side of 'a ?? b' is evaluated only if a is nil
+        if expr.op == Token::Coalesce {
+            let jump = self.emit(Instruction::JumpIfNotNil(0));
+            self.value(expr.right.as_ref())?;
+            self.patch(jump);
+            return Ok(());
+        }
+        self.value(expr.right.as_ref())?;
+        let op = Self::binary_op(&expr.op)?;
+        self.emit(Instruction::Binary(op));
+        self.value_pushed = true;
+        Ok(())
+    }
+
+    fn visit_index_expression(&mut self, expr: &IndexExpression) -> ExpressionVisitResult {
+        self.value(expr.object.as_ref())?;
+        self.value(expr.index.as_ref())?;
+        self.emit(Instruction::Index);
+        self.value_pushed = true;
+        Ok(())
+    }
+
+    fn visit_field_expression(&mut self, expr: &FieldExpression) -> ExpressionVisitResult {
+        self.value(expr.object.as_ref())?;
+        let name = self.chunk.add_name(&expr.field);
+        self.emit(Instruction::Field(name));
+        self.value_pushed = true;
+        Ok(())
+    }
+
+    fn visit_assignment_expression(&mut self, expr: &AssignmentExpression) -> ExpressionVisitResult {
+        self.value(expr.value.as_ref())?;
+        let var = self.var(&expr.name);
+        self.emit(Instruction::Store(var));
+        self.value_pushed = false;
+        Ok(())
+    }
+
+    fn visit_compound_assignment_expression(&mut self, expr: &CompoundAssignmentExpression) -> ExpressionVisitResult {
+        let (var, n) = self.target_path(&expr.target)?;
+        let op = Self::binary_op(&expr.op)?;
+        if n == 0 {
+            self.emit(Instruction::Load(var));
+        } else {
+            self.emit(Instruction::LoadPath(var, n));
+        }
+        self.value(expr.value.as_ref())?;
+        self.emit(Instruction::Binary(op));
+        if n == 0 {
+            self.emit(Instruction::Store(var));
+        } else {
+            self.emit(Instruction::StorePath(var, n));
+        }
+        self.value_pushed = false;
+        Ok(())
+    }
+
+    fn visit_multiple_assignment_expression(&mut self, expr: &MultipleAssignmentExpression) -> ExpressionVisitResult {
+        for value in expr.values.iter() {
+            self.value(value.as_ref())?;
+        }
+        if expr.values.len() != 1 || expr.targets.len() != 1 {
+            self.emit(Instruction::Unpack { values: expr.values.len(), targets: expr.targets.len() });
+        }
+        for target in expr.targets.iter() {
+            self.assign_to_target(target)?;
+        }
+        self.value_pushed = false;
+        Ok(())
+    }
+
+    fn visit_if_expression(&mut self, expr: &IfExpression) -> ExpressionVisitResult {
+        self.value(expr.if_expr.as_ref())?;
+        let false_jump = self.emit(Instruction::JumpIfFalse(0));
+        self.block(&expr.true_expression)?;
+        if expr.false_expression.is_empty() {
+            self.patch(false_jump);
+        } else {
+            let end_jump = self.emit(Instruction::Jump(0));
+            self.patch(false_jump);
+            self.block(&expr.false_expression)?;
+            self.patch(end_jump);
+        }
+        self.value_pushed = false;
+        Ok(())
+    }
+
+    fn visit_match_expression(&mut self, expr: &MatchExpression) -> ExpressionVisitResult {
+        // the matched value stays on the stack, while patterns of arms are checked
+        self.value(expr.value.as_ref())?;
+        let mut end_jumps = Vec::new();
+        for arm in expr.arms.iter() {
+            let mut matched_jumps = Vec::new();
+            for pattern in arm.patterns.iter() {
+                match pattern {
+                    MatchPattern::Wildcard => {
+                        matched_jumps.push(self.emit(Instruction::Jump(0)));
+                    }
+                    MatchPattern::Binding(name) => {
+                        self.emit(Instruction::Dup);
+                        let var = self.var(name);
+                        self.emit(Instruction::Store(var));
+                        matched_jumps.push(self.emit(Instruction::Jump(0)));
+                    }
+                    MatchPattern::Value(value) => {
+                        self.emit(Instruction::Dup);
+                        self.value(value.as_ref())?;
+                        self.emit(Instruction::Equal);
+                        matched_jumps.push(self.emit(Instruction::JumpIfTrue(0)));
+                    }
+                    MatchPattern::Range { low, high, inclusive } => {
+                        self.emit(Instruction::Dup);
+                        self.value(low.as_ref())?;
+                        self.value(high.as_ref())?;
+                        self.emit(Instruction::InRange { inclusive: *inclusive });
+                        matched_jumps.push(self.emit(Instruction::JumpIfTrue(0)));
+                    }
+                }
+            }
+            let next_arm_jump = self.emit(Instruction::Jump(0));
+            for jump in matched_jumps {
+                self.patch(jump);
+            }
+            let mut guard_jump = None;
+            if let Some(guard) = &arm.guard {
+                self.value(guard.as_ref())?;
+                guard_jump = Some(self.emit(Instruction::JumpIfFalse(0)));
+            }
+            self.emit(Instruction::Pop);
+            self.block(&arm.body)?;
+            end_jumps.push(self.emit(Instruction::Jump(0)));
+            self.patch(next_arm_jump);
+            if let Some(jump) = guard_jump {
+                self.patch(jump);
+            }
+        }
+        self.emit(Instruction::NoMatch);
+        for jump in end_jumps {
+            self.patch(jump);
+        }
+        self.value_pushed = false;
+        Ok(())
+    }
+
+    fn visit_while_expression(&mut self, expr: &WhileExpression) -> ExpressionVisitResult {
+        let start = self.chunk.code.len();
+        self.value(expr.while_expr.as_ref())?;
+        let end_jump = self.emit(Instruction::JumpIfFalse(0));
+        self.block(&expr.body_exprs)?;
+        self.emit(Instruction::Jump(start));
+        self.patch(end_jump);
+        self.value_pushed = false;
+        Ok(())
+    }
+
+    fn visit_for_expression(&mut self, expr: &ForExpression) -> ExpressionVisitResult {
+        // the counter and the upper bound stay on the stack during the loop
+        self.value(expr.l_bound.as_ref())?;
+        self.value(expr.r_bound.as_ref())?;
+        let var = self.var(&expr.var_name);
+        self.emit(Instruction::ForInit(var));
+        let check = self.emit(Instruction::ForCheck(0));
+        self.stack_depth += 2;
+        self.block(&expr.body_exprs)?;
+        self.stack_depth -= 2;
+        self.emit(Instruction::ForStep(var));
+        self.emit(Instruction::Jump(check));
+        self.patch(check);
+        self.value_pushed = false;
+        Ok(())
+    }
+
+    fn visit_function_def_expression(&mut self, expr: &FunctionDefExpression) -> ExpressionVisitResult {
+        // arguments get the first slots, the variadic argument is after them
+        let mut locals = SlotTable::new();
+        for arg in expr.args.iter().chain(expr.rest.iter()) {
+            if locals.get(arg).is_some() {
+                return Err(format!("duplicate argument '{}' of function {}", arg, expr.name));
+            }
+            locals.slot(arg);
+        }
+        let mut compiler = Compiler {
+            chunk: Chunk::default(),
+            locals: Some(locals),
+            function: Some(expr.name.clone()),
+            globals: &mut *self.globals,
+            value_pushed: false,
+            keep_result: false,
+            check_stack: self.check_stack,
+            stack_depth: 0,
+        };
+        // default values are evaluated on call for arguments, that are not given
+        let required = expr.args.len() - expr.defaults.len();
+        for (i, default) in expr.defaults.iter().enumerate() {
+            let var = Var::Local(required + i);
+            let jump = compiler.emit(Instruction::JumpIfAssigned(var, 0));
+            compiler.value(default.as_ref())?;
+            compiler.emit(Instruction::Store(var));
+            compiler.patch(jump);
+        }
+        compiler.value_pushed = false;
+        compiler.block(&expr.body)?;
+        // function returns nil without return statement
+        compiler.constant(ValueVariant::Nil);
+        compiler.emit(Instruction::Return);
+        let mut chunk = compiler.chunk;
+        chunk.local_names = compiler.locals.map(|locals| locals.names().to_vec()).unwrap_or_default();
+        let signature = Signature {
+            name: expr.name.clone(),
+            args: expr.args.clone(),
+            required,
+            rest: expr.rest.clone(),
+        };
+        self.chunk.functions.push(Rc::new(Function { signature, chunk: Rc::new(chunk) }));
+        self.emit(Instruction::DefineFunction(self.chunk.functions.len() - 1));
+        self.value_pushed = false;
+        Ok(())
+    }
+
+    fn visit_function_call_expression(&mut self, expr: &FunctionCallExpression) -> ExpressionVisitResult {
+        self.call_args(&expr.args)?;
+        let name = self.chunk.add_name(&expr.name);
+        if expr.kwargs.is_empty() {
+            self.emit_call(Instruction::Call { name, argc: expr.args.len() }, expr.line);
+        } else {
+            let mut keywords = Vec::with_capacity(expr.kwargs.len());
+            for (keyword, value) in expr.kwargs.iter() {
+                self.value(value.as_ref())?;
+                keywords.push(keyword.clone());
+            }
+            self.chunk.key_lists.push(keywords);
+            let keywords = self.chunk.key_lists.len() - 1;
+            self.emit_call(Instruction::CallWithKeywords { name, argc: expr.args.len(), keywords }, expr.line);
+        }
+        self.value_pushed = true;
+        Ok(())
+    }
+
+    fn visit_method_call_expression(&mut self, expr: &MethodCallExpression) -> ExpressionVisitResult {
+        self.call_args(&expr.args)?;
+        let var = self.var(&expr.self_name);
+        let name = self.chunk.add_name(&expr.method_name);
+        self.emit_call(Instruction::CallNamed { var, name, argc: expr.args.len() }, expr.line);
+        self.value_pushed = true;
+        Ok(())
+    }
+
+    fn visit_anonymous_method_call_expression(&mut self, expr: &AnonymousMethodExpression) -> ExpressionVisitResult {
+        self.value(expr.self_expr.as_ref())?;
+        self.call_args(&expr.args)?;
+        let name = self.chunk.add_name(&expr.method_name);
+        self.emit_call(Instruction::CallMethod { name, argc: expr.args.len() }, expr.line);
+        self.value_pushed = true;
+        Ok(())
+    }
+
+    fn visit_return_expression(&mut self, expr: &ReturnExpression) -> ExpressionVisitResult {
+        self.value(expr.expr.as_ref())?;
+        if self.locals.is_some() {
+            self.tail_call();
+            self.emit(Instruction::Return);
+        } else {
+            // return statement outside of functions only evaluates its value
+            self.emit(Instruction::Pop);
+        }
+        self.value_pushed = false;
+        Ok(())
+    }
+
+    fn visit_import_expression(&mut self, expr: &ImportExpression) -> ExpressionVisitResult {
+        let path = self.chunk.add_name(&expr.path);
+        let namespace = self.chunk.add_name(&expr.namespace);
+        self.emit(Instruction::Import { path, namespace });
+        self.value_pushed = false;
+        Ok(())
+    }
+
+    fn visit_statement_list_expression(&mut self, expr: &StatementListExpression) -> ExpressionVisitResult {
+        let keep_result = mem::take(&mut self.keep_result);
+        self.value_pushed = false;
+        for (i, statement) in expr.statement_list.iter().enumerate() {
+            if keep_result && i + 1 == expr.statement_list.len() {
+                statement.accept(self)?;
+            } else {
+                self.statement(statement.as_ref())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ex_core::{tokenize, Parser};
+
+    fn compile(program: &str, globals: &mut SlotTable) -> Chunk {
+        let expr = Parser::new(&tokenize(program).unwrap()).parse().unwrap();
+        Compiler::compile(expr.as_ref(), globals, false, false).unwrap()
+    }
+
+    #[test]
+    fn slots_test() {
+        let mut globals = SlotTable::new();
+        let chunk = compile("a = 1\nfn f(x, y) {\n z = x + a\n return z\n}\nb = a", &mut globals);
+        assert_eq!(globals.names(), ["a", "b"]);
+        assert_eq!(chunk.code, vec![
+            Instruction::Constant(0),
+            Instruction::Store(Var::Global(0)),
+            Instruction::DefineFunction(0),
+            Instruction::Load(Var::Global(0)),
+            Instruction::Store(Var::Global(1)),
+            Instruction::End,
+        ]);
+        // variables of functions are local, even if there is the global variable with the same name
+        let function = &chunk.functions[0];
+        assert_eq!(function.chunk.local_names, ["x", "y", "a", "z"]);
+        assert_eq!(function.chunk.code, vec![
+            Instruction::Load(Var::Local(0)),
+            Instruction::Load(Var::Local(2)),
+            Instruction::Binary(BinaryOp::Plus),
+            Instruction::Store(Var::Local(3)),
+            Instruction::Load(Var::Local(3)),
+            Instruction::Return,
+            Instruction::Constant(0),
+            Instruction::Return,
+        ]);
+
+        // global slots are kept between compilations
+        compile("c = b\na = c", &mut globals);
+        assert_eq!(globals.names(), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn constant_pool_test() {
+        let chunk = compile("a = \"s\" + \"s\"\nb = [1, 1.0, 1, \"s\"]", &mut SlotTable::new());
+        assert_eq!(chunk.constants, vec![ValueVariant::String("s".into()), ValueVariant::Integer(1), ValueVariant::Float(1.0)]);
+    }
+
+    #[test]
+    fn statement_values_are_dropped_test() {
+        let chunk = compile("f(1)\nwhile true {\n 2\n}", &mut SlotTable::new());
+        assert_eq!(chunk.code, vec![
+            Instruction::Constant(0),
+            Instruction::Call { name: 0, argc: 1 },
+            Instruction::Pop,
+            Instruction::Constant(1),
+            Instruction::JumpIfFalse(8),
+            Instruction::Constant(2),
+            Instruction::Pop,
+            Instruction::Jump(3),
+            Instruction::End,
+        ]);
+    }
+
+    #[test]
+    fn tail_call_test() {
+        let chunk = compile("fn f(n) {\n return f(n - 1)\n}\nfn g(n) {\n return f(n)\n}\nfn h(n) {\n return h(n) + 1\n}", &mut SlotTable::new());
+        let last_calls: Vec<Instruction> = chunk.functions.iter()
+            .map(|function| function.chunk.code[function.chunk.code.len() - 4])
+            .collect();
+        // only calls of the function itself in return statements are tail calls
+        assert_eq!(last_calls, vec![
+            Instruction::TailCall { name: 0, argc: 1, keywords: None },
+            Instruction::Call { name: 0, argc: 1 },
+            Instruction::Binary(BinaryOp::Plus),
+        ]);
+    }
+
+    #[test]
+    fn check_stack_test() {
+        let expr = Parser::new(&tokenize("a = 1\nfor i in [0, a] {\n a\n}").unwrap()).parse().unwrap();
+        let chunk = Compiler::compile(expr.as_ref(), &mut SlotTable::new(), false, true).unwrap();
+        // the counter and the bound of the loop are on the stack in its body
+        let checks: Vec<&Instruction> = chunk.code.iter().filter(|instruction| matches!(instruction, Instruction::CheckStack(_))).collect();
+        assert_eq!(checks, [&Instruction::CheckStack(0), &Instruction::CheckStack(2), &Instruction::CheckStack(0)]);
+    }
+
+    #[test]
+    fn duplicate_argument_test() {
+        let expr = Parser::new(&tokenize("fn f(a, a) {\n}").unwrap()).parse().unwrap();
+        let err = Compiler::compile(expr.as_ref(), &mut SlotTable::new(), false, false).err().unwrap();
+        assert_eq!(err, "duplicate argument 'a' of function f");
+    }
+}
@@ -8,11 +8,19 @@ pub trait ExpressionVisitor {
     fn visit_int_literal_expression(&mut self, expr: &IntLiteralExpression) -> ExpressionVisitResult;
     fn visit_string_literal_expression(&mut self, expr: &StringLiteralExpression) -> ExpressionVisitResult;
     fn visit_bool_literal_expression(&mut self, expr: &BoolLiteralExpression) -> ExpressionVisitResult;
+    fn visit_nil_literal_expression(&mut self, expr: &NilLiteralExpression) -> ExpressionVisitResult;
+    fn visit_list_expression(&mut self, expr: &ListExpression) -> ExpressionVisitResult;
+    fn visit_map_expression(&mut self, expr: &MapExpression) -> ExpressionVisitResult;
     fn visit_name_expression(&mut self, expr: &NameExpression) -> ExpressionVisitResult;
     fn visit_unary_expression(&mut self, expr: &UnaryExpression) -> ExpressionVisitResult;
     fn visit_binary_expression(&mut self, expr: &BinaryExpression) -> ExpressionVisitResult;
+    fn visit_index_expression(&mut self, expr: &IndexExpression) -> ExpressionVisitResult;
+    fn visit_field_expression(&mut self, expr: &FieldExpression) -> ExpressionVisitResult;
     fn visit_assignment_expression(&mut self, expr: &AssignmentExpression) -> ExpressionVisitResult;
+    fn visit_compound_assignment_expression(&mut self, expr: &CompoundAssignmentExpression) -> ExpressionVisitResult;
+    fn visit_multiple_assignment_expression(&mut self, expr: &MultipleAssignmentExpression) -> ExpressionVisitResult;
     fn visit_if_expression(&mut self, expr: &IfExpression) ->ExpressionVisitResult;
+    fn visit_match_expression(&mut self, expr: &MatchExpression) -> ExpressionVisitResult;
     fn visit_while_expression(&mut self, expr: &WhileExpression) -> ExpressionVisitResult;
     fn visit_for_expression(&mut self, expr: &ForExpression) -> ExpressionVisitResult;
     fn visit_function_def_expression(&mut self, expr: &FunctionDefExpression) -> ExpressionVisitResult;
@@ -20,6 +28,7 @@ pub trait ExpressionVisitor {
     fn visit_method_call_expression(&mut self, expr: &MethodCallExpression) -> ExpressionVisitResult;
     fn visit_anonymous_method_call_expression(&mut self, expr: &AnonymousMethodExpression) -> ExpressionVisitResult;
     fn visit_return_expression(&mut self, expr: &ReturnExpression) -> ExpressionVisitResult;
+    fn visit_import_expression(&mut self, expr: &ImportExpression) -> ExpressionVisitResult;
     fn visit_statement_list_expression(&mut self, expr: &StatementListExpression) -> ExpressionVisitResult;
 }
 
@@ -92,6 +101,44 @@ impl Expression for BoolLiteralExpression {
     }
 }
 
+#[derive(Clone)]
+pub struct NilLiteralExpression;
+impl Expression for NilLiteralExpression {
+    fn accept(&self, visitor : & mut dyn ExpressionVisitor) ->  ExpressionVisitResult {
+        visitor.visit_nil_literal_expression(self)
+    }
+}
+
+#[derive(Clone)]
+pub struct ListExpression {
+    pub items: Vec<Box<dyn Expression>>
+}
+impl ListExpression {
+    pub fn new(items: Vec<Box<dyn Expression>>) -> Self {
+        ListExpression { items }
+    }
+}
+impl Expression for ListExpression {
+    fn accept(&self, visitor : & mut dyn ExpressionVisitor) ->  ExpressionVisitResult {
+        visitor.visit_list_expression(self)
+    }
+}
+
+#[derive(Clone)]
+pub struct MapExpression {
+    pub items: Vec<(String, Box<dyn Expression>)>
+}
+impl MapExpression {
+    pub fn new(items: Vec<(String, Box<dyn Expression>)>) -> Self {
+        MapExpression { items }
+    }
+}
+impl Expression for MapExpression {
+    fn accept(&self, visitor : & mut dyn ExpressionVisitor) ->  ExpressionVisitResult {
+        visitor.visit_map_expression(self)
+    }
+}
+
 #[derive(Clone)]
 pub struct UnaryExpression {
     pub op: Token,
@@ -144,6 +191,88 @@ impl Expression for AssignmentExpression {
     }
 }
 
+/// left side of the assignment
+#[derive(Clone)]
+pub enum AssignmentTarget {
+    /// a = value
+    Name(String),
+    /// xs[0] = value
+    Index(Box<AssignmentTarget>, Box<dyn Expression>),
+    /// p.x = value
+    Field(Box<AssignmentTarget>, String),
+    /// [x, y] = list
+    List(Vec<AssignmentTarget>),
+    /// {name, age} = map
+    Map(Vec<String>),
+}
+
+#[derive(Clone)]
+pub struct CompoundAssignmentExpression {
+    pub target: AssignmentTarget,
+    pub op: Token,
+    pub value: Box<dyn Expression>
+}
+impl CompoundAssignmentExpression {
+    pub fn new(target: AssignmentTarget, op: Token, value: Box<dyn Expression>) -> Self {
+        CompoundAssignmentExpression { target, op, value }
+    }
+}
+impl Expression for CompoundAssignmentExpression {
+    fn accept(&self, visitor : & mut dyn ExpressionVisitor) ->  ExpressionVisitResult {
+        visitor.visit_compound_assignment_expression(self)
+    }
+}
+
+/// assignment of the values to the targets, all values are evaluated before assigning,
+/// so 'a, b = b, a' swaps the values
+#[derive(Clone)]
+pub struct MultipleAssignmentExpression {
+    pub targets: Vec<AssignmentTarget>,
+    pub values: Vec<Box<dyn Expression>>
+}
+impl MultipleAssignmentExpression {
+    pub fn new(targets: Vec<AssignmentTarget>, values: Vec<Box<dyn Expression>>) -> Self {
+        MultipleAssignmentExpression { targets, values }
+    }
+}
+impl Expression for MultipleAssignmentExpression {
+    fn accept(&self, visitor : & mut dyn ExpressionVisitor) ->  ExpressionVisitResult {
+        visitor.visit_multiple_assignment_expression(self)
+    }
+}
+
+#[derive(Clone)]
+pub struct IndexExpression {
+    pub object: Box<dyn Expression>,
+    pub index: Box<dyn Expression>
+}
+impl IndexExpression {
+    pub fn new(object: Box<dyn Expression>, index: Box<dyn Expression>) -> Self {
+        IndexExpression { object, index }
+    }
+}
+impl Expression for IndexExpression {
+    fn accept(&self, visitor : & mut dyn ExpressionVisitor) ->  ExpressionVisitResult {
+        visitor.visit_index_expression(self)
+    }
+}
+
+#[derive(Clone)]
+pub struct FieldExpression {
+    pub object: Box<dyn Expression>,
+    pub field: String
+}
+impl FieldExpression {
+    pub fn new(object: Box<dyn Expression>, field: String) -> Self {
+        FieldExpression { object, field }
+    }
+}
+impl Expression for FieldExpression {
+    fn accept(&self, visitor : & mut dyn ExpressionVisitor) ->  ExpressionVisitResult {
+        visitor.visit_field_expression(self)
+    }
+}
+
 #[derive(Clone)]
 pub struct StatementListExpression {
     pub statement_list: Vec<Box<dyn Expression>>,
@@ -179,11 +308,15 @@ impl Expression for NameExpression {
 #[derive(Clone)]
 pub struct FunctionCallExpression {
     pub name: String, 
-    pub args: Vec<Box<dyn Expression>> 
+    pub args: Vec<Box<dyn Expression>>,
+    /// keyword arguments, that follow positional arguments
+    pub kwargs: Vec<(String, Box<dyn Expression>)>,
+    /// line of the call, that is shown in tracebacks
+    pub line: usize
 }
 impl FunctionCallExpression {
-    pub fn new(name: String, args : Vec<Box<dyn Expression>> ) -> Self {
-        FunctionCallExpression { name: name, args: args }
+    pub fn new(name: String, args : Vec<Box<dyn Expression>>, kwargs: Vec<(String, Box<dyn Expression>)>, line: usize) -> Self {
+        FunctionCallExpression { name: name, args: args, kwargs: kwargs, line: line }
     }
 }
 impl Expression for FunctionCallExpression {
@@ -196,11 +329,12 @@ impl Expression for FunctionCallExpression {
 pub struct MethodCallExpression {
     pub self_name: String,
     pub method_name: String,
-    pub args: Vec<Box<dyn Expression>>
+    pub args: Vec<Box<dyn Expression>>,
+    pub line: usize
 }
 impl MethodCallExpression {
-    pub fn new(self_name: String, method_name: String, args: Vec<Box<dyn Expression>>) -> Self {
-        MethodCallExpression { self_name: self_name, method_name : method_name, args: args }
+    pub fn new(self_name: String, method_name: String, args: Vec<Box<dyn Expression>>, line: usize) -> Self {
+        MethodCallExpression { self_name: self_name, method_name : method_name, args: args, line: line }
     }
 }
 impl Expression for MethodCallExpression {
@@ -213,11 +347,12 @@ impl Expression for MethodCallExpression {
 pub struct AnonymousMethodExpression {
     pub self_expr: Box<dyn Expression>,
     pub method_name: String,
-    pub args: Vec<Box<dyn Expression>>
+    pub args: Vec<Box<dyn Expression>>,
+    pub line: usize
 }
 impl AnonymousMethodExpression {
-    pub fn new(self_expr: Box<dyn Expression>, method_name: String, args: Vec<Box<dyn Expression>>) -> Self {
-        AnonymousMethodExpression { self_expr: self_expr,  method_name: method_name, args: args }
+    pub fn new(self_expr: Box<dyn Expression>, method_name: String, args: Vec<Box<dyn Expression>>, line: usize) -> Self {
+        AnonymousMethodExpression { self_expr: self_expr,  method_name: method_name, args: args, line: line }
     }
 }
 impl Expression for AnonymousMethodExpression {
@@ -291,17 +426,59 @@ impl Expression for IfExpression {
     }
 }
 
+#[derive(Clone)]
+pub enum MatchPattern {
+    /// _
+    Wildcard,
+    /// name, that gets the matched value
+    Binding(String),
+    /// literal value, like 1 or "a"
+    Value(Box<dyn Expression>),
+    /// 1..5 or 1..=5 when 'inclusive'
+    Range { low: Box<dyn Expression>, high: Box<dyn Expression>, inclusive: bool },
+}
+
+#[derive(Clone)]
+pub struct MatchArm {
+    /// alternatives of the arm, separated by '|'
+    pub patterns: Vec<MatchPattern>,
+    pub guard: Option<Box<dyn Expression>>,
+    pub body: Vec<Box<dyn Expression>>
+}
+
+#[derive(Clone)]
+pub struct MatchExpression {
+    pub value: Box<dyn Expression>,
+    pub arms: Vec<MatchArm>
+}
+impl MatchExpression {
+    pub fn new(value: Box<dyn Expression>, arms: Vec<MatchArm>) -> Self {
+        MatchExpression { value, arms }
+    }
+}
+impl Expression for MatchExpression {
+    fn accept(&self, visitor : & mut dyn ExpressionVisitor) ->  ExpressionVisitResult {
+        visitor.visit_match_expression(self)
+    }
+}
+
 #[derive(Clone)]
 pub struct FunctionDefExpression {
     pub name: String,
     pub args: Vec<String>,
+    /// default values of the last arguments
+    pub defaults: Vec<Box<dyn Expression>>,
+    /// name of the variadic argument, that gets the list of extra arguments
+    pub rest: Option<String>,
     pub body: Vec<Box<dyn Expression>>
 }
 impl FunctionDefExpression {
-    pub fn new(name: String, args: Vec<String>, body: Vec<Box<dyn Expression>>) -> Self {
+    pub fn new(name: String, args: Vec<String>, defaults: Vec<Box<dyn Expression>>, rest: Option<String>, body: Vec<Box<dyn Expression>>) -> Self {
         FunctionDefExpression {
             name : name,
             args : args,
+            defaults : defaults,
+            rest : rest,
             body : body
         }
     }
@@ -326,3 +503,21 @@ impl Expression for ReturnExpression {
         visitor.visit_return_expression(self)
     }
 }
+
+#[derive(Clone)]
+pub struct ImportExpression {
+    /// path of the module file as written in the script, 'import utils' imports "utils.ex"
+    pub path: String,
+    /// name, that the module functions are accessed with, like 'u' in 'u.func()'
+    pub namespace: String
+}
+impl ImportExpression {
+    pub fn new(path: String, namespace: String) -> Self {
+        ImportExpression { path, namespace }
+    }
+}
+impl Expression for ImportExpression {
+    fn accept(&self, visitor : & mut dyn ExpressionVisitor) ->  ExpressionVisitResult {
+        visitor.visit_import_expression(self)
+    }
+}
@@ -1,16 +1,60 @@
 use core::fmt;
 
-use crate::ex_std::{FunctionRepository, IOFunctionRepo, StdFuncMap, StdMethodsMap, IntMethods, StdMethodsRepository, StringMethods};
-use std::collections::HashMap;
-use std::mem::Discriminant;
+use crate::ex_std::{FunctionRepository, IOFunctionRepo, EnvFunctionRepo, ProcessFunctionRepo, ExpandFunctionRepo, JsonFunctionRepo, StdFuncMap, StdFuncSignatures, StdMethodsMap, StdMutMethod, StdMutMethodsMap, IntMethods, StdMethodsRepository, StringMethods, ListMethods, MapMethods, BytesMethods, CharMethods, ProcessMethods, ProcessRef, RegexCache, RegexFunctionRepo, StdRegexFuncMap};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
-use super::{expressions::{FunctionDefExpression, Expression, ExpressionVisitResult, ExpressionVisitor}, tokenizer::Token};
+use super::{load_module, ModuleLoader, ModuleRef};
+use super::bytecode::{BinaryOp, Chunk, Function, Instruction, SlotTable, Var};
+use super::compiler::Compiler;
+use super::resolver::{Environment, Resolver};
+use super::expressions::Expression;
+/// Value of the script. Strings, lists and maps are shared between copies of the value,
+/// so assignment and passing of the value are cheap. Changing the list or the map copies it,
+/// if it's shared (copy on write), so values never alias each other
 #[derive(Clone, PartialEq, Debug)]
 pub enum ValueVariant {
-    String(String),
+    String(Rc<str>),
+    /// unicode scalar value, items of strings are chars
+    Char(char),
+    /// raw bytes of process output or files, that may be not valid utf-8 text
+    Bytes(Rc<Vec<u8>>),
     Integer(i64),
     Float(f64), 
-    Bool(bool)
+    Bool(bool),
+    List(Rc<Vec<ValueVariant>>),
+    Map(Rc<BTreeMap<String, ValueVariant>>),
+    Process(ProcessRef),
+    Nil
+}
+impl ValueVariant {
+    /// name of the value type, std methods are registered for this name
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            ValueVariant::String(_) => "string",
+            ValueVariant::Char(_) => "char",
+            ValueVariant::Bytes(_) => "bytes",
+            ValueVariant::Integer(_) => "int",
+            ValueVariant::Float(_) => "float",
+            ValueVariant::Bool(_) => "bool",
+            ValueVariant::List(_) => "list",
+            ValueVariant::Map(_) => "map",
+            ValueVariant::Process(_) => "process",
+            ValueVariant::Nil => "nil"
+        }
+    }
+
+    /// writes value as an item of list or map, strings are quoted
+    fn fmt_item(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueVariant::String(s) => write!(f, "{:?}", s),
+            ValueVariant::Char(c) => write!(f, "{:?}", c),
+            _ => write!(f, "{}", self)
+        }
+    }
 }
 impl fmt::Display for ValueVariant {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -18,6 +62,12 @@ impl fmt::Display for ValueVariant {
             ValueVariant::String(s) => {
                 write!(f, "{}", s)
             }
+            ValueVariant::Char(c) => {
+                write!(f, "{}", c)
+            }
+            ValueVariant::Bytes(b) => {
+                write!(f, "b\"{}\"", b.escape_ascii())
+            }
             ValueVariant::Integer(i) => {
                 write!(f, "{}", i)
             }
@@ -27,518 +77,1142 @@ impl fmt::Display for ValueVariant {
             ValueVariant::Bool(b) => {
                 write!(f, "{}", b)
             }
+            ValueVariant::List(l) => {
+                write!(f, "[")?;
+                for (i, item) in l.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    item.fmt_item(f)?;
+                }
+                write!(f, "]")
+            }
+            ValueVariant::Map(m) => {
+                write!(f, "{{")?;
+                for (i, (key, item)) in m.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{:?}: ", key)?;
+                    item.fmt_item(f)?;
+                }
+                write!(f, "}}")
+            }
+            ValueVariant::Process(p) => {
+                write!(f, "<process {}>", p.borrow().pid())
+            }
+            ValueVariant::Nil => {
+                write!(f, "nil")
+            }
         }
     }
 }
 
-type UserFuncMap = HashMap<String, FunctionDefExpression>;
-type ValueScope = HashMap<String, ValueVariant>;
 
+type UserFuncMap = HashMap<String, Rc<Function>>;
+
+/// maximum number of nested calls of user functions, if EX_MAX_CALL_DEPTH environment variable is not set
+const DEFAULT_MAX_CALL_DEPTH: usize = 10000;
+
+/// function of the interpreter, that calls a user function and returns its error instead of stopping the script
+const TRY_CALL: &str = "try_call";
+
+/// Call frame of the compiled code
+struct Frame {
+    /// called function, None for the top level code
+    function: Option<Rc<Function>>,
+    chunk: Rc<Chunk>,
+    ip: usize,
+    locals: Vec<Option<ValueVariant>>,
+    /// length of the values stack before the call, values above it are dropped on return
+    stack_len: usize,
+}
+
+/// Stack VM, that runs the bytecode compiled from the syntax tree
 pub struct Interpreter {
     values_stack: Vec<ValueVariant>,
-    var_scopes: Vec<ValueScope>,
+    frames: Vec<Frame>,
+    global_slots: SlotTable,
+    /// values of global variables by their slots, None if the variable is not assigned yet
+    globals: Vec<Option<ValueVariant>>,
     std_funcs: StdFuncMap,
-    std_methods: HashMap<Discriminant<ValueVariant>, StdMethodsMap>,
-    user_funcs: UserFuncMap
+    /// std functions, that use compiled patterns of 'regex_cache'
+    regex_funcs: StdRegexFuncMap,
+    regex_cache: RegexCache,
+    std_func_signatures: StdFuncSignatures,
+    std_methods: HashMap<&'static str, StdMethodsMap>,
+    /// std methods, that change the value they are called on
+    std_mut_methods: HashMap<&'static str, StdMutMethodsMap>,
+    user_funcs: UserFuncMap,
+    /// imported modules by their namespaces
+    modules: HashMap<String, ModuleRef>,
+    module_loader: Rc<RefCell<ModuleLoader>>,
+    /// directory of the interpreted file, modules are searched relative to it
+    script_dir: Option<PathBuf>,
+    /// maximum number of nested calls of user functions
+    max_call_depth: usize,
 }
 type InterpResult = Result<(), String>;
+/// true if the string is exactly the char, so chars can be compared with string literals like ","
+fn is_char_string(c: char, s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next() == Some(c) && chars.next().is_none()
+}
+
+/// item of the list by the index or value of the map by the key
+fn element<'a>(value: &'a ValueVariant, key: &ValueVariant) -> Result<&'a ValueVariant, String> {
+    match (value, key) {
+        (ValueVariant::List(items), ValueVariant::Integer(i)) => {
+            usize::try_from(*i).ok()
+                .and_then(|i| items.get(i))
+                .ok_or(format!("index {} is out of range for list of length {}", i, items.len()))
+        }
+        (ValueVariant::Map(map), ValueVariant::String(k)) => {
+            map.get(&**k).ok_or(format!("no key '{}' in map", k))
+        }
+        (value, key) => Err(format!("can't index {} value with {} value", value.type_name(), key.type_name())),
+    }
+}
+
+/// item of the list, value of the map, byte of the bytes or char of the string,
+/// items of bytes and strings are not stored in values, so they are returned by value
+fn item(value: &ValueVariant, key: &ValueVariant) -> Result<ValueVariant, String> {
+    match (value, key) {
+        (ValueVariant::Bytes(bytes), ValueVariant::Integer(i)) => {
+            usize::try_from(*i).ok()
+                .and_then(|i| bytes.get(i))
+                .map(|byte| ValueVariant::Integer(*byte as i64))
+                .ok_or(format!("index {} is out of range for bytes of length {}", i, bytes.len()))
+        }
+        (ValueVariant::String(s), ValueVariant::Integer(i)) => {
+            usize::try_from(*i).ok()
+                .and_then(|i| s.chars().nth(i))
+                .map(ValueVariant::Char)
+                .ok_or(format!("index {} is out of range for string of length {}", i, s.chars().count()))
+        }
+        _ => element(value, key).cloned(),
+    }
+}
+
+/// mutable item of the list by the index or value of the map by the key,
+/// the list or the map is copied, if it's shared with other values
+fn element_mut<'a>(value: &'a mut ValueVariant, key: &ValueVariant) -> Result<&'a mut ValueVariant, String> {
+    match (value, key) {
+        (ValueVariant::List(items), ValueVariant::Integer(i)) => {
+            let len = items.len();
+            usize::try_from(*i).ok()
+                .and_then(|i| Rc::make_mut(items).get_mut(i))
+                .ok_or(format!("index {} is out of range for list of length {}", i, len))
+        }
+        (ValueVariant::Map(map), ValueVariant::String(k)) => {
+            Rc::make_mut(map).get_mut(&**k).ok_or(format!("no key '{}' in map", k))
+        }
+        (value @ (ValueVariant::String(_) | ValueVariant::Bytes(_)), _) => Err(format!("items of {} value can't be assigned", value.type_name())),
+        (value, key) => Err(format!("can't index {} value with {} value", value.type_name(), key.type_name())),
+    }
+}
+
+
+/// slot of the local variable of the current frame or of the global variable
+fn var_slot<'a>(frames: &'a mut [Frame], globals: &'a mut [Option<ValueVariant>], var: Var) -> &'a mut Option<ValueVariant> {
+    match var {
+        Var::Local(slot) => &mut frames.last_mut().unwrap().locals[slot],
+        Var::Global(slot) => &mut globals[slot],
+    }
+}
+
 impl Interpreter {
     pub fn new() -> Interpreter {
-        let mut std_func_repos = Vec::new();
+        Self::with_module_loader(Rc::new(RefCell::new(ModuleLoader::new())), None)
+    }
+
+    /// interpreter of the module, that shares loaded modules with the interpreter of the script
+    pub fn with_module_loader(module_loader: Rc<RefCell<ModuleLoader>>, script_dir: Option<PathBuf>) -> Interpreter {
+        let mut std_func_repos: Vec<Box<dyn FunctionRepository>> = Vec::new();
         std_func_repos.push(Box::new(IOFunctionRepo::new()));
+        std_func_repos.push(Box::new(EnvFunctionRepo::new()));
+        std_func_repos.push(Box::new(ProcessFunctionRepo::new()));
+        std_func_repos.push(Box::new(ExpandFunctionRepo::new()));
+        std_func_repos.push(Box::new(JsonFunctionRepo::new()));
         let mut std_fucs : StdFuncMap = StdFuncMap::new(); 
+        let mut std_func_signatures = StdFuncSignatures::new();
         for repo in std_func_repos.iter() {
             for (fname, f) in repo.get_functions() {
                 std_fucs.insert(fname, f);
             }
+            std_func_signatures.extend(repo.get_signatures());
         }
+        let regex_repo = RegexFunctionRepo::new();
+        std_func_signatures.extend(regex_repo.get_signatures());
+        std_func_signatures.insert(TRY_CALL.to_string(), format!("{}(name, args...)", TRY_CALL));
 
-        let mut std_methods : HashMap<Discriminant<ValueVariant>,StdMethodsMap> = HashMap::new();
+        let mut std_methods : HashMap<&'static str, StdMethodsMap> = HashMap::new();
+        let mut std_mut_methods : HashMap<&'static str, StdMutMethodsMap> = HashMap::new();
         let mut std_methods_repos: Vec<Box<dyn StdMethodsRepository>> = Vec::new();
         std_methods_repos.push(Box::new(IntMethods::new()));
         std_methods_repos.push(Box::new(StringMethods::new()));
+        std_methods_repos.push(Box::new(ListMethods::new()));
+        std_methods_repos.push(Box::new(MapMethods::new()));
+        std_methods_repos.push(Box::new(BytesMethods::new()));
+        std_methods_repos.push(Box::new(CharMethods::new()));
+        std_methods_repos.push(Box::new(ProcessMethods::new()));
         
         for methods_repo in std_methods_repos.iter() {
-            std_methods.insert(methods_repo.get_diterminant(), methods_repo.get_methods());
+            std_methods.insert(methods_repo.get_type_name(), methods_repo.get_methods());
+            std_mut_methods.insert(methods_repo.get_type_name(), methods_repo.get_mut_methods());
         }
 
 
-        let mut var_scopes : Vec<ValueScope> = Vec::new();
-        // global value scope
-        var_scopes.push(ValueScope::new());
         return Interpreter {
             values_stack : vec![], 
-            var_scopes: var_scopes,
+            frames: Vec::new(),
+            global_slots: SlotTable::new(),
+            globals: Vec::new(),
             std_funcs : std_fucs,
+            regex_funcs: regex_repo.get_functions(),
+            regex_cache: RegexCache::default(),
+            std_func_signatures,
             std_methods : std_methods,
-            user_funcs : HashMap::new()
+            std_mut_methods,
+            user_funcs : HashMap::new(),
+            modules: HashMap::new(),
+            module_loader,
+            script_dir,
+            max_call_depth: env::var("EX_MAX_CALL_DEPTH").ok()
+                .and_then(|depth| depth.parse().ok())
+                .unwrap_or(DEFAULT_MAX_CALL_DEPTH),
         };
     } 
+
+    /// sets the path of the interpreted script, modules are imported relative to its directory
+    pub fn set_script_path(&mut self, path: &Path) {
+        self.script_dir = path.parent().map(Path::to_path_buf);
+        self.module_loader.borrow_mut().set_root(path);
+    }
+
     pub fn interp_expr(&mut self, expr : Box<dyn Expression>) -> InterpResult {
-        if let Err(err_msg) = expr.accept(self) {
-            Err(err_msg)
-        } else {
-            Ok(())
-        }
+        self.execute(expr.as_ref(), false).map(|_| ())
+    }
+
+    /// interprets 'expr' and returns the value of its last statement, if it's an expression
+    pub fn interp_expr_with_result(&mut self, expr : Box<dyn Expression>) -> Result<Option<ValueVariant>, String> {
+        self.execute(expr.as_ref(), true)
+    }
+
+    /// interprets 'expr' like interp_expr_with_result, then restores global variables, functions and modules,
+    /// so the code leaves no definitions in the interpreter
+    pub fn interp_expr_isolated(&mut self, expr : Box<dyn Expression>) -> Result<Option<ValueVariant>, String> {
+        let global_slots = self.global_slots.clone();
+        let globals = self.globals.clone();
+        let user_funcs = self.user_funcs.clone();
+        let modules = self.modules.clone();
+        let result = self.execute(expr.as_ref(), true);
+        self.global_slots = global_slots;
+        self.globals = globals;
+        self.user_funcs = user_funcs;
+        self.modules = modules;
+        result
+    }
+
+    /// analyses 'expr' before interpretation and returns warnings about undefined names,
+    /// unused variables and calls of functions with the wrong number of arguments
+    pub fn check(&self, expr: &dyn Expression) -> Vec<String> {
+        let env = Environment {
+            assigned_globals: self.global_slots.names().iter().zip(self.globals.iter())
+                .filter(|(_, value)| value.is_some())
+                .map(|(name, _)| name.clone())
+                .collect(),
+            std_funcs: self.std_func_names().cloned().collect(),
+            user_funcs: self.user_funcs.iter().map(|(name, f)| (name.clone(), f.signature.clone())).collect(),
+            modules: self.modules.keys().cloned().collect(),
+        };
+        Resolver::resolve(expr, &env).warnings
+    }
+
+    /// global variables, that have values, sorted by name
+    pub fn get_vars(&self) -> Vec<(String, ValueVariant)> {
+        let mut vars : Vec<(String, ValueVariant)> = self.global_slots.names().iter().zip(self.globals.iter())
+            .filter_map(|(name, value)| value.clone().map(|value| (name.clone(), value)))
+            .collect();
+        vars.sort_by(|l, r| l.0.cmp(&r.0));
+        vars
+    }
+
+    /// names of std and user functions, sorted
+    pub fn get_func_names(&self) -> Vec<String> {
+        let mut names : Vec<String> = self.std_func_names().chain(self.user_funcs.keys()).cloned().collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// every std function has a signature, try_call too
+    fn std_func_names(&self) -> impl Iterator<Item = &String> {
+        self.std_func_signatures.keys()
+    }
+
+    /// signatures of std functions, sorted by function name
+    pub fn get_std_func_signatures(&self) -> Vec<String> {
+        let mut names : Vec<&String> = self.std_func_names().collect();
+        names.sort();
+        names.into_iter()
+            .map(|name| self.std_func_signatures.get(name).cloned().unwrap_or(format!("{}(...)", name)))
+            .collect()
+    }
+
+    /// signatures of user functions, sorted by function name
+    pub fn get_user_func_signatures(&self) -> Vec<String> {
+        let mut funcs : Vec<&Rc<Function>> = self.user_funcs.values().collect();
+        funcs.sort_by(|l, r| l.signature.name.cmp(&r.signature.name));
+        funcs.into_iter().map(|f| f.signature.to_string()).collect()
+    }
+
+    /// names of std methods for 'type_name' or for all types if 'type_name' is None, sorted
+    pub fn get_method_names(&self, type_name: Option<&str>) -> Vec<String> {
+        let mut names : Vec<String> = self.std_methods.iter()
+            .filter(|(t, _)| type_name.is_none() || type_name == Some(**t))
+            .flat_map(|(_, methods)| methods.keys().cloned())
+            .chain(self.std_mut_methods.iter()
+                .filter(|(t, _)| type_name.is_none() || type_name == Some(**t))
+                .flat_map(|(_, methods)| methods.keys().cloned()))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
     }
 
-    pub fn _get_var_value(&mut self, name: &String) -> Option<ValueVariant> {
-        assert!(self.var_scopes.len() >= 1);
-        self.var_scopes.last().unwrap().get(name).cloned()
+    /// names of std methods, that change the value they are called on, sorted
+    pub fn get_mut_method_names(&self) -> Vec<String> {
+        let mut names : Vec<String> = self.std_mut_methods.values().flat_map(|methods| methods.keys().cloned()).collect();
+        names.sort();
+        names.dedup();
+        names
     }
 
-    fn _add_var_scope(&mut self) {
-        assert!(self.var_scopes.len() >= 1);
-        self.var_scopes.push(ValueScope::new());
+    pub fn _get_var_value(&mut self, name: &str) -> Option<ValueVariant> {
+        let slot = self.global_slots.get(name)?;
+        self.globals.get(slot).cloned().flatten()
     }
 
-    fn _drop_var_scope(&mut self) {
-        assert!(self.var_scopes.len() >= 2);
-        self.var_scopes.pop();
+    pub fn add_var(&mut self, name: &str, var: &ValueVariant) {
+        let slot = self.global_slots.slot(name);
+        self.globals.resize(self.global_slots.len(), None);
+        self.globals[slot] = Some(var.clone());
     }
 
-    fn _get_var(&mut self, name: &String) -> Option<&mut ValueVariant> {
-        assert!(self.var_scopes.len() >= 1);
-        for scope in self.var_scopes.iter_mut().rev() {
-            if let Some(res) = scope.get_mut(name) {
-                return Some(res);
+    /// compiles 'expr' and runs it
+    fn execute(&mut self, expr: &dyn Expression, keep_result: bool) -> Result<Option<ValueVariant>, String> {
+        // the balance of the values stack is verified in debug builds
+        let chunk = Compiler::compile(expr, &mut self.global_slots, keep_result, cfg!(debug_assertions))?;
+        self.globals.resize(self.global_slots.len(), None);
+        self.run_frame(None, Rc::new(chunk), Vec::new())
+    }
+
+    /// runs the chunk in the new frame until it returns,
+    /// errors inside user functions get the traceback of calls
+    fn run_frame(&mut self, function: Option<Rc<Function>>, chunk: Rc<Chunk>, locals: Vec<Option<ValueVariant>>) -> Result<Option<ValueVariant>, String> {
+        let base = self.frames.len();
+        let stack_len = self.values_stack.len();
+        self.frames.push(Frame { function, chunk, ip: 0, locals, stack_len });
+        let result = self.run(base).map_err(|err_msg| {
+            if self.frames[base..].iter().any(|frame| frame.function.is_some()) {
+                format!("{}\n{}", err_msg, self.traceback(base))
+            } else {
+                err_msg
             }
+        });
+        if result.is_err() {
+            self.frames.truncate(base);
+            self.values_stack.truncate(stack_len);
         }
-        None
+        result
     }
 
-    fn add_var(&mut self, name: &String, var: &ValueVariant) {
-        assert!(self.var_scopes.len() >= 1);
-        let n = self.var_scopes.len();
-        self.var_scopes[n - 1].insert(name.clone(), var.clone());
+    /// pushes the frame of the user function call
+    fn push_call(&mut self, function: Rc<Function>, locals: Vec<Option<ValueVariant>>) -> InterpResult {
+        // the top level code has its own frame
+        if self.frames.len() > self.max_call_depth {
+            return Err(format!("maximum call depth {} exceeded", self.max_call_depth));
+        }
+        let stack_len = self.values_stack.len();
+        self.frames.push(Frame { chunk: function.chunk.clone(), function: Some(function), ip: 0, locals, stack_len });
+        Ok(())
     }
 
-    fn get_current_stack_value(&mut self) -> Result<ValueVariant, String> {
-        if let Some(value) = self.values_stack.pop() {
-            return Ok(value);
+    /// calls the std or user function with arguments on the stack, values of keyword arguments
+    /// are above positional ones. The tail call of the user function reuses the current frame
+    fn call_function(&mut self, chunk: &Chunk, name: usize, argc: usize, keywords: Option<usize>, tail: bool) -> InterpResult {
+        let name = &chunk.names[name];
+        let keywords = keywords.map(|i| chunk.key_lists[i].as_slice()).unwrap_or_default();
+        let keyword_values = self.pop_values(keywords.len())?;
+        let mut args = self.pop_values(argc)?;
+        if self.std_func_signatures.contains_key(name) {
+            if !keywords.is_empty() {
+                args = self.std_func_args(name, args, keywords, keyword_values)?;
+            }
+            if name == TRY_CALL {
+                let value = self.try_call(args)?;
+                self.values_stack.push(value);
+                return Ok(());
+            }
+            let value = match self.std_funcs.get(name) {
+                Some(f) => f(&args),
+                None => self.regex_funcs[name](&mut self.regex_cache, &args),
+            };
+            let value = value.map_err(|err_msg| format!("Error with function {} : {}", name, err_msg))?;
+            self.values_stack.push(value.unwrap_or(ValueVariant::Nil));
+            return Ok(());
         }
-        Err("Expected value on stack".to_string())
+        let Some(function) = self.user_funcs.get(name).cloned() else {
+            return Err(format!("function {} not defined", name));
+        };
+        let locals = Self::function_locals(&function, args, keywords, keyword_values)?;
+        if !tail {
+            return self.push_call(function, locals);
+        }
+        let frame = self.frames.last_mut().unwrap();
+        self.values_stack.truncate(frame.stack_len);
+        frame.chunk = function.chunk.clone();
+        frame.function = Some(function);
+        frame.ip = 0;
+        frame.locals = locals;
+        Ok(())
     }
 
-    fn value_variant_to_int(&mut self, v : &ValueVariant) -> Result<i64,String> {
-        match v {
-            ValueVariant::Integer(i) => {
-                Ok(*i)
+    /// try_call(name, args...) calls the user function and returns the list [result, nil],
+    /// or [nil, error message], if the call fails, like when it exceeds the maximum call depth
+    fn try_call(&mut self, mut args: Vec<ValueVariant>) -> Result<ValueVariant, String> {
+        if args.is_empty() {
+            return Err(format!("Error with function {} : expected function name", TRY_CALL));
+        }
+        let name = match args.remove(0) {
+            ValueVariant::String(name) => name,
+            v => return Err(format!("Error with function {} : expected function name, find {}", TRY_CALL, v)),
+        };
+        let Some(function) = self.user_funcs.get(&*name).cloned() else {
+            return Err(format!("function {} not defined", name));
+        };
+        let base = self.frames.len();
+        let stack_len = self.values_stack.len();
+        let result = Self::function_locals(&function, args, &[], Vec::new())
+            .and_then(|locals| self.push_call(function, locals))
+            .and_then(|_| self.run(base));
+        let (value, error) = match result {
+            Ok(value) => (value.unwrap_or(ValueVariant::Nil), ValueVariant::Nil),
+            Err(err_msg) => {
+                self.frames.truncate(base);
+                self.values_stack.truncate(stack_len);
+                (ValueVariant::Nil, ValueVariant::String(err_msg.into()))
             }
-            _ => {
-                Err("expected int".to_string())
+        };
+        Ok(ValueVariant::List(Rc::new(vec![value, error])))
+    }
+
+    /// calls in frames from 'base' with their lines, the innermost call is the last one,
+    /// repeated calls of the recursion are collapsed
+    fn traceback(&self, base: usize) -> String {
+        let mut calls: Vec<(String, usize)> = Vec::new();
+        for frame in self.frames[base..].iter() {
+            let location = match &frame.function {
+                Some(function) => format!("function {}", function.signature.name),
+                None => String::from("top level code"),
+            };
+            let call = match self.call_at(&frame.chunk, frame.ip - 1) {
+                Some((line, callee)) => format!("  {}, line {}: call of {}", location, line, callee),
+                None => format!("  {}", location),
+            };
+            match calls.last_mut() {
+                Some((last, repeated)) if *last == call => *repeated += 1,
+                _ => calls.push((call, 0)),
             }
         }
+        let mut traceback = String::from("traceback (most recent call last):");
+        for (call, repeated) in calls {
+            traceback.push('\n');
+            traceback.push_str(&call);
+            if repeated > 0 {
+                traceback.push_str(&format!("\n  ... repeated {} more times", repeated));
+            }
+        }
+        traceback
     }
 
-    fn call_std_func(&mut self, expr: &crate::ex_core::expressions::FunctionCallExpression) -> ExpressionVisitResult {
-            
-            let f = self.std_funcs.get(&expr.name).unwrap().clone();
-            for arg_expr in expr.args.iter() {
-                arg_expr.accept(self)?;
+    /// line and name of the function or method, that is called by the instruction at 'pos'
+    fn call_at(&self, chunk: &Chunk, pos: usize) -> Option<(usize, String)> {
+        let line = chunk.lines.get(pos).copied().filter(|line| *line > 0)?;
+        let callee = match chunk.code[pos] {
+            Instruction::Call { name, .. } | Instruction::CallWithKeywords { name, .. } | Instruction::CallMethod { name, .. } => chunk.names[name].clone(),
+            Instruction::CallNamed { var, name, .. } => {
+                let self_name = match var {
+                    Var::Local(slot) => &chunk.local_names[slot],
+                    Var::Global(slot) => &self.global_slots.names()[slot],
+                };
+                format!("{}.{}", self_name, chunk.names[name])
             }
-            let mut args : Vec<ValueVariant> = Vec::new();
-            let arg_count = &expr.args.len();
-            for _ in 0..*arg_count {
-                if let Some(value) = self.values_stack.pop() {
-                    args.insert(0, value);
-                } else {
-                    return Err(String::from("exptected value in stack"));
-                }
+            _ => return None,
+        };
+        Some((line, callee))
+    }
+
+    /// arguments of the std function call, keyword arguments are placed by argument names of the function signature,
+    /// like "pretty" in "json_stringify(value, [pretty])"
+    fn std_func_args(&self, name: &str, args: Vec<ValueVariant>, keywords: &[String], keyword_values: Vec<ValueVariant>) -> Result<Vec<ValueVariant>, String> {
+        let signature = self.std_func_signatures.get(name).map(String::as_str).unwrap_or_default();
+        let arg_names: Vec<&str> = signature.split_once('(')
+            .and_then(|(_, args)| args.strip_suffix(')'))
+            .map(|args| args.split(", ").map(|arg| arg.trim_matches(['[', ']'])).collect())
+            .unwrap_or_default();
+        let mut slots: Vec<Option<ValueVariant>> = args.into_iter().map(Some).collect();
+        for (keyword, value) in keywords.iter().zip(keyword_values) {
+            // the variadic argument, like "values...", can't be given by name
+            let Some(i) = arg_names.iter().position(|arg| arg == keyword) else {
+                return Err(format!("function {} has no argument '{}'", name, keyword));
+            };
+            if slots.len() <= i {
+                slots.resize(i + 1, None);
             }
-            let f_result = f(&args);
-            match f_result {
-                Ok(f_return_value) => {
-                    if let Some(val) = f_return_value {
-                        self.values_stack.push(val);
-                    }
-                }
-                Err(err_msg) => {
-                    return Err(format!("Error with function {} : {}", &expr.name, err_msg));                
-                }
+            if slots[i].is_some() {
+                return Err(format!("argument '{}' of function {} is given twice", keyword, name));
             }
-            Ok(())
+            slots[i] = Some(value);
         }
-        
-    fn call_user_func(&mut self, expr: &crate::ex_core::expressions::FunctionCallExpression) -> ExpressionVisitResult {
-        assert!(self.var_scopes.len() >= 1);
-        self.var_scopes.push(ValueScope::new()); 
-        let user_f = self.user_funcs.get(&expr.name).unwrap().clone();
-        for arg in expr.args.iter() {
-            arg.accept(self)?;
-        }
-        let mut user_args = user_f.args;
-        
-        user_args.reverse();
-        for arg_name in user_args.iter() {
-            if let Some(arg_value) = self.values_stack.pop() {
-                let n = self.var_scopes.len();
-                self.var_scopes[n - 1].insert(arg_name.clone(), arg_value);
+        slots.into_iter().enumerate()
+            .map(|(i, slot)| slot.ok_or_else(|| format!("missing argument '{}' of function {}", arg_names[i], name)))
+            .collect()
+    }
+
+    /// local variables of the function call, the arguments are the first ones, then the variadic argument.
+    /// Arguments with default values, that are not given, stay unassigned
+    fn function_locals(function: &Function, mut args: Vec<ValueVariant>, keywords: &[String], keyword_values: Vec<ValueVariant>) -> Result<Vec<Option<ValueVariant>>, String> {
+        let signature = &function.signature;
+        signature.check_args(args.len(), keywords)?;
+        let mut locals = vec![None; function.chunk.local_names.len()];
+        if signature.rest.is_some() {
+            let rest = args.split_off(args.len().min(signature.args.len()));
+            locals[signature.args.len()] = Some(ValueVariant::List(Rc::new(rest)));
+        }
+        for (local, arg) in locals.iter_mut().zip(args) {
+            *local = Some(arg);
+        }
+        for (keyword, value) in keywords.iter().zip(keyword_values) {
+            if let Some(i) = signature.args.iter().position(|arg| arg == keyword) {
+                locals[i] = Some(value);
             }
         }
-        for expr in user_f.body.iter() {
-            expr.accept(self)?;
+        Ok(locals)
+    }
+
+    /// calls the user function of the module with already evaluated arguments
+    pub fn call_module_func(&mut self, name: &str, args: Vec<ValueVariant>) -> Result<ValueVariant, String> {
+        let Some(function) = self.user_funcs.get(name).cloned() else {
+            return Err(format!("function {} not defined in module", name));
+        };
+        let locals = Self::function_locals(&function, args, &[], Vec::new())?;
+        let value = self.run_frame(Some(function.clone()), function.chunk.clone(), locals)?;
+        Ok(value.unwrap_or(ValueVariant::Nil))
+    }
+
+    /// global variables of the module, 'u.x' is the variable 'x' of the module imported as 'u'
+    fn module_vars(module: &ModuleRef) -> Result<ValueVariant, String> {
+        let module = module.try_borrow().map_err(|_| String::from("module can't access itself by its namespace"))?;
+        let vars = module.global_slots.names().iter().zip(module.globals.iter())
+            .filter_map(|(name, value)| value.clone().map(|value| (name.clone(), value)))
+            .collect();
+        Ok(ValueVariant::Map(Rc::new(vars)))
+    }
+
+    fn var_name(&self, var: Var) -> &str {
+        match var {
+            Var::Local(slot) => &self.frames.last().unwrap().chunk.local_names[slot],
+            Var::Global(slot) => &self.global_slots.names()[slot],
+        }
+    }
+
+    fn var_value(&self, var: Var) -> Option<&ValueVariant> {
+        match var {
+            Var::Local(slot) => self.frames.last().unwrap().locals[slot].as_ref(),
+            Var::Global(slot) => self.globals[slot].as_ref(),
+        }
+    }
+
+    fn set_var(&mut self, var: Var, value: ValueVariant) {
+        match var {
+            Var::Local(slot) => self.frames.last_mut().unwrap().locals[slot] = Some(value),
+            Var::Global(slot) => self.globals[slot] = Some(value),
         }
-        self.var_scopes.pop();
-        Ok(())
     }
 
+    /// value of the variable, that is not assigned, it's the namespace of the module or the error
+    fn unassigned_var_value(&self, var: Var) -> Result<ValueVariant, String> {
+        let name = self.var_name(var);
+        match self.modules.get(name) {
+            Some(module) => Self::module_vars(module),
+            None => Err(format!("unknown name '{}'", name)),
+        }
+    }
+
+    /// item of the variable by the path of keys
+    fn get_path_value(&self, var: Var, path: &[ValueVariant]) -> Result<ValueVariant, String> {
+        let Some(mut current) = self.var_value(var) else {
+            return Err(format!("unknown name '{}'", self.var_name(var)));
+        };
+        let Some((last, parent_path)) = path.split_last() else {
+            return Ok(current.clone());
+        };
+        for key in parent_path {
+            current = element(current, key)?;
+        }
+        item(current, last)
+    }
 
-    fn call_method(&mut self, this_value : &ValueVariant, method_name: &String, args: &Vec<Box<dyn Expression>>) -> ExpressionVisitResult {
-        let discr = std::mem::discriminant(this_value);
-        if !self.std_methods.contains_key(&discr) {
-            return Err(format!("method for {} not supported", this_value.to_string()));
+    /// sets the item of the variable, new keys are added to maps, list items must exist
+    fn set_path_value(&mut self, var: Var, path: &[ValueVariant], value: ValueVariant) -> InterpResult {
+        let Some((last, parent_path)) = path.split_last() else {
+            self.set_var(var, value);
+            return Ok(());
+        };
+        let slot = var_slot(&mut self.frames, &mut self.globals, var);
+        let Some(mut current) = slot.as_mut() else {
+            return Err(format!("unknown name '{}'", self.var_name(var)));
+        };
+        for key in parent_path {
+            current = element_mut(current, key)?;
         }
-        let methods_map = self.std_methods.get(&discr).unwrap().clone();
-        if let Some(f) = methods_map.get(method_name) {
-            for arg_expr in args.iter() {
-                arg_expr.accept(self)?;
+        match (current, last) {
+            (ValueVariant::Map(map), ValueVariant::String(key)) => {
+                Rc::make_mut(map).insert(key.to_string(), value);
             }
-            let mut parsed_args : Vec<ValueVariant> = Vec::new();
-            let arg_count = &args.len();
-            for _ in 0..*arg_count {
-                if let Some(value) = self.values_stack.pop() {
-                    parsed_args.insert(0, value);
-                } else {
-                    return Err(String::from("exptected value in stack"));
-                }
+            (current, key) => {
+                *element_mut(current, key)? = value;
             }
-            let f_result = f(&this_value, &parsed_args);
-            match f_result {
-                Ok(f_return_value) => {
-                    if let Some(val) = f_return_value {
-                        self.values_stack.push(val);
-                    }
-                }
-                Err(err_msg) => {
-                    return Err(format!("Error with method {} : {}", &method_name, err_msg));                
-                }
-            }
-        } else {
-            return Err(format!("unknows method {} for {}", method_name, this_value.to_string()));
         }
         Ok(())
     }
 
-}
+    fn get_current_stack_value(&mut self) -> Result<ValueVariant, String> {
+        if let Some(value) = self.values_stack.pop() {
+            return Ok(value);
+        }
+        Err("Expected value on stack".to_string())
+    }
 
+    /// pops 'n' values from the stack in the order they were pushed
+    fn pop_values(&mut self, n: usize) -> Result<Vec<ValueVariant>, String> {
+        if self.values_stack.len() < n {
+            return Err(String::from("exptected value in stack"));
+        }
+        Ok(self.values_stack.split_off(self.values_stack.len() - n))
+    }
 
+    fn value_variant_to_int(v : &ValueVariant) -> Result<i64,String> {
+        match v {
+            ValueVariant::Integer(i) => {
+                Ok(*i)
+            }
+            _ => {
+                Err("expected int".to_string())
+            }
+        }
+    }
 
-impl ExpressionVisitor for Interpreter {
-    fn visit_float_literal_expression(&mut self, expr: &crate::ex_core::expressions::FloatLiteralExpression) -> ExpressionVisitResult {
-        self.values_stack.push(ValueVariant::Float(expr.f));
-        Ok(())
+    fn call_method(&self, this_value : &ValueVariant, method_name: &str, args: Vec<ValueVariant>) -> Result<ValueVariant, String> {
+        let Some(methods) = self.std_methods.get(this_value.type_name()) else {
+            return Err(format!("method for {} not supported", this_value));
+        };
+        let Some(f) = methods.get(method_name) else {
+            if self.mut_method(this_value, method_name).is_some() {
+                return Err(format!("method {} changes the value, it can be called only on a variable, not on a temporary {} value", method_name, this_value.type_name()));
+            }
+            return Err(format!("unknows method {} for {}", method_name, this_value));
+        };
+        Self::method_result(method_name, f(this_value, &args))
     }
 
-    fn visit_int_literal_expression(&mut self, expr: &super::IntLiteralExpression) -> ExpressionVisitResult {
-        self.values_stack.push(ValueVariant::Integer(expr.i));
-        Ok(())
+    /// std method, that changes 'this_value'
+    fn mut_method(&self, this_value: &ValueVariant, method_name: &str) -> Option<StdMutMethod> {
+        self.std_mut_methods.get(this_value.type_name())?.get(method_name).copied()
     }
 
-    fn visit_string_literal_expression(&mut self, expr: &crate::ex_core::expressions::StringLiteralExpression) -> ExpressionVisitResult {
-        self.values_stack.push(ValueVariant::String(expr.s.clone()));
-        Ok(())
+    /// calls the std method, that changes the value of the variable in place
+    fn call_mut_method(&mut self, f: StdMutMethod, var: Var, method_name: &str, args: Vec<ValueVariant>) -> Result<ValueVariant, String> {
+        let this_value = var_slot(&mut self.frames, &mut self.globals, var).as_mut().ok_or("method of unassigned variable")?;
+        Self::method_result(method_name, f(this_value, &args))
     }
 
-    fn visit_bool_literal_expression(&mut self, expr: &super::BoolLiteralExpression) -> ExpressionVisitResult {
-        self.values_stack.push(ValueVariant::Bool(expr.b));
-        Ok(())
+    fn method_result(method_name: &str, result: Result<Option<ValueVariant>, String>) -> Result<ValueVariant, String> {
+        match result {
+            Ok(f_return_value) => Ok(f_return_value.unwrap_or(ValueVariant::Nil)),
+            Err(err_msg) => Err(format!("Error with method {} : {}", method_name, err_msg)),
+        }
     }
 
-    fn visit_name_expression(&mut self, expr: &crate::ex_core::expressions::NameExpression) -> ExpressionVisitResult {
-        assert!(self.var_scopes.len() >= 1);
-        if let Some(value) = self.var_scopes.last().unwrap().get(&expr.name) {
-            self.values_stack.push(value.clone());
-            return Ok(());
-        } else {
-            return Err(format!("unknown name '{}'", &expr.name));
-        }
-    }
-    fn visit_unary_expression(&mut self, un_expr: &crate::ex_core::expressions::UnaryExpression) -> ExpressionVisitResult {
-        un_expr.expr.accept(self)?;
-        let op = un_expr.op.clone();
-        if let Some(val) = self.values_stack.pop() {
-            match val {
-                ValueVariant::Float(f) => {
-                    match op {
-                        Token::Plus => {
-                            self.values_stack.push(ValueVariant::Float(f));  
-                        } 
-                        Token::Minus => {
-                            self.values_stack.push(ValueVariant::Float(-f));            
+    /// calls the function of the module imported with 'namespace'
+    fn call_module(&mut self, namespace: &str, name: &str, args: Vec<ValueVariant>) -> Result<ValueVariant, String> {
+        let Some(module) = self.modules.get(namespace).cloned() else {
+            return Err(format!("call method with unknown object '{}'", namespace));
+        };
+        let mut module = module.try_borrow_mut().map_err(|_| format!("module '{}' can't call itself by its namespace", namespace))?;
+        module.call_module_func(name, args)
+            .map_err(|err_msg| format!("Error with function {}.{} : {}", namespace, name, err_msg))
+    }
+
+    /// runs instructions of the frames above 'base' until the frame at 'base' returns
+    fn run(&mut self, base: usize) -> Result<Option<ValueVariant>, String> {
+        loop {
+            let frame = self.frames.last_mut().unwrap();
+            let instruction = frame.chunk.code[frame.ip];
+            frame.ip += 1;
+            match instruction {
+                Instruction::Constant(i) => {
+                    let value = frame.chunk.constants[i].clone();
+                    self.values_stack.push(value);
+                }
+                Instruction::Pop => {
+                    self.get_current_stack_value()?;
+                }
+                Instruction::Dup => {
+                    let value = self.values_stack.last().cloned().ok_or("Expected value on stack")?;
+                    self.values_stack.push(value);
+                }
+                Instruction::Rotate(n) => {
+                    let value = self.values_stack.remove(self.values_stack.len() - 1 - n);
+                    self.values_stack.push(value);
+                }
+                Instruction::Load(var) => {
+                    let value = match self.var_value(var) {
+                        Some(value) => value.clone(),
+                        None => self.unassigned_var_value(var)?,
+                    };
+                    self.values_stack.push(value);
+                }
+                Instruction::Store(var) => {
+                    let value = self.get_current_stack_value()?;
+                    self.set_var(var, value);
+                }
+                Instruction::LoadPath(var, n) => {
+                    let path = self.values_stack[self.values_stack.len() - n..].to_vec();
+                    let value = self.get_path_value(var, &path)?;
+                    self.values_stack.push(value);
+                }
+                Instruction::StorePath(var, n) => {
+                    let value = self.get_current_stack_value()?;
+                    let path = self.pop_values(n)?;
+                    self.set_path_value(var, &path, value)?;
+                }
+                Instruction::BuildList(n) => {
+                    let items = self.pop_values(n)?;
+                    self.values_stack.push(ValueVariant::List(Rc::new(items)));
+                }
+                Instruction::BuildMap(i) => {
+                    let chunk = frame.chunk.clone();
+                    let keys = &chunk.key_lists[i];
+                    let items = self.pop_values(keys.len())?;
+                    self.values_stack.push(ValueVariant::Map(Rc::new(keys.iter().cloned().zip(items).collect())));
+                }
+                Instruction::Negate | Instruction::Positive => {
+                    let negate = instruction == Instruction::Negate;
+                    let value = match self.get_current_stack_value()? {
+                        ValueVariant::Float(f) => ValueVariant::Float(if negate { -f } else { f }),
+                        ValueVariant::Integer(i) if negate => {
+                            ValueVariant::Integer(i.checked_neg().ok_or(format!("integer overflow in -{}", i))?)
                         }
-                        _ => {
-                            return Err(format!("unsupported unary op {}", op.to_string()));
+                        ValueVariant::Integer(i) => ValueVariant::Integer(i),
+                        _ => return Err("for now unary operation supported only with float types".to_string()),
+                    };
+                    self.values_stack.push(value);
+                }
+                Instruction::Binary(op) => {
+                    let r = self.get_current_stack_value()?;
+                    let l = self.get_current_stack_value()?;
+                    let result = self.binary_op(op, l, r)?;
+                    self.values_stack.push(result);
+                }
+                Instruction::Equal => {
+                    let r = self.get_current_stack_value()?;
+                    let l = self.get_current_stack_value()?;
+                    let equal = match (&l, &r) {
+                        (ValueVariant::Char(c), ValueVariant::String(s)) | (ValueVariant::String(s), ValueVariant::Char(c)) => is_char_string(*c, s),
+                        _ => l == r,
+                    };
+                    self.values_stack.push(ValueVariant::Bool(equal));
+                }
+                Instruction::InRange { inclusive } => {
+                    let high = self.get_current_stack_value()?;
+                    let low = self.get_current_stack_value()?;
+                    let value = self.get_current_stack_value()?;
+                    let as_float = |value: &ValueVariant| match value {
+                        ValueVariant::Integer(i) => Some(*i as f64),
+                        ValueVariant::Float(f) => Some(*f),
+                        _ => None,
+                    };
+                    let (Some(low), Some(high)) = (as_float(&low), as_float(&high)) else {
+                        return Err(String::from("bounds of range pattern must be numbers"));
+                    };
+                    let in_range = match as_float(&value) {
+                        Some(v) if inclusive => low <= v && v <= high,
+                        Some(v) => low <= v && v < high,
+                        None => false,
+                    };
+                    self.values_stack.push(ValueVariant::Bool(in_range));
+                }
+                Instruction::Index => {
+                    let index = self.get_current_stack_value()?;
+                    let object = self.get_current_stack_value()?;
+                    let item = item(&object, &index)?;
+                    self.values_stack.push(item);
+                }
+                Instruction::Field(i) => {
+                    let field = &frame.chunk.names[i];
+                    match self.values_stack.pop() {
+                        Some(ValueVariant::Map(map)) => {
+                            let value = map.get(field).cloned().ok_or(format!("no field '{}' in map", field))?;
+                            self.values_stack.push(value);
                         }
+                        Some(value) => return Err(format!("can't get field '{}' of {} value", field, value.type_name())),
+                        None => return Err("Expected value on stack".to_string()),
                     }
                 }
-                ValueVariant::Integer(i) => {
-                    match op {
-                        Token::Plus => {
-                            self.values_stack.push(ValueVariant::Integer(i));  
-                        } 
-                        Token::Minus => {
-                            self.values_stack.push(ValueVariant::Integer(-i));            
+                Instruction::Jump(target) => {
+                    frame.ip = target;
+                }
+                Instruction::JumpIfFalse(target) | Instruction::JumpIfTrue(target) => {
+                    let expected = matches!(instruction, Instruction::JumpIfTrue(_));
+                    match self.values_stack.pop() {
+                        Some(ValueVariant::Bool(b)) => {
+                            if b == expected {
+                                self.frames.last_mut().unwrap().ip = target;
+                            }
                         }
-                        _ => {
-                            return Err(format!("unsupported unary op {}", op.to_string()));
+                        Some(value) => return Err(format!("condition must be bool, got {}", value.type_name())),
+                        None => return Err("Expected value on stack".to_string()),
+                    }
+                }
+                Instruction::JumpIfNotNil(target) => {
+                    if self.values_stack.last() != Some(&ValueVariant::Nil) {
+                        frame.ip = target;
+                    } else {
+                        self.values_stack.pop();
+                    }
+                }
+                Instruction::JumpIfAssigned(var, target) => {
+                    if self.var_value(var).is_some() {
+                        self.frames.last_mut().unwrap().ip = target;
+                    }
+                }
+                Instruction::ForInit(var) => {
+                    let len = self.values_stack.len();
+                    let l_bound = Self::value_variant_to_int(&self.values_stack[len - 2])?;
+                    Self::value_variant_to_int(&self.values_stack[len - 1])?;
+                    self.set_var(var, ValueVariant::Integer(l_bound));
+                }
+                Instruction::ForCheck(target) => {
+                    let len = self.values_stack.len();
+                    if let [ValueVariant::Integer(counter), ValueVariant::Integer(r_bound)] = self.values_stack[len - 2..] {
+                        if counter >= r_bound {
+                            self.values_stack.truncate(len - 2);
+                            frame.ip = target;
                         }
                     }
-                },
-                _ => {
-                    return Err("for now unary operation supported only with float types".to_string());    
+                }
+                Instruction::ForStep(var) => {
+                    let len = self.values_stack.len();
+                    if let ValueVariant::Integer(counter) = &mut self.values_stack[len - 2] {
+                        *counter += 1;
+                        let counter = *counter;
+                        self.set_var(var, ValueVariant::Integer(counter));
+                    }
+                }
+                Instruction::Unpack { values, targets } => {
+                    let mut items = self.pop_values(values)?;
+                    // a, b = pair
+                    if values == 1 && targets > 1 {
+                        items = match items.remove(0) {
+                            ValueVariant::List(items) => Rc::unwrap_or_clone(items),
+                            value => return Err(format!("can't destructure {} value to {} targets", value.type_name(), targets)),
+                        };
+                    }
+                    if items.len() != targets {
+                        return Err(format!("can't assign {} values to {} targets", items.len(), targets));
+                    }
+                    self.values_stack.extend(items.into_iter().rev());
+                }
+                Instruction::UnpackList(n) => {
+                    let value = self.get_current_stack_value()?;
+                    let ValueVariant::List(items) = value else {
+                        return Err(format!("can't destructure {} value as list", value.type_name()));
+                    };
+                    if items.len() != n {
+                        return Err(format!("can't destructure list of {} items to {} targets", items.len(), n));
+                    }
+                    self.values_stack.extend(Rc::unwrap_or_clone(items).into_iter().rev());
+                }
+                Instruction::UnpackMap(i) => {
+                    let chunk = frame.chunk.clone();
+                    let value = self.get_current_stack_value()?;
+                    let ValueVariant::Map(map) = value else {
+                        return Err(format!("can't destructure {} value as map", value.type_name()));
+                    };
+                    let mut map = Rc::unwrap_or_clone(map);
+                    let mut items = Vec::with_capacity(chunk.key_lists[i].len());
+                    for name in chunk.key_lists[i].iter() {
+                        items.push(map.remove(name).ok_or(format!("no key '{}' in map to destructure", name))?);
+                    }
+                    self.values_stack.extend(items.into_iter().rev());
+                }
+                Instruction::NoMatch => {
+                    let value = self.get_current_stack_value()?;
+                    return Err(format!("non-exhaustive match: no arm matches value {}", value));
+                }
+                Instruction::Call { name, argc } => {
+                    let chunk = frame.chunk.clone();
+                    self.call_function(&chunk, name, argc, None, false)?;
+                }
+                Instruction::CallWithKeywords { name, argc, keywords } => {
+                    let chunk = frame.chunk.clone();
+                    self.call_function(&chunk, name, argc, Some(keywords), false)?;
+                }
+                Instruction::TailCall { name, argc, keywords } => {
+                    let chunk = frame.chunk.clone();
+                    self.call_function(&chunk, name, argc, keywords, true)?;
+                }
+                Instruction::CallMethod { name, argc } => {
+                    let chunk = frame.chunk.clone();
+                    let args = self.pop_values(argc)?;
+                    let this_value = self.get_current_stack_value()?;
+                    let value = self.call_method(&this_value, &chunk.names[name], args)?;
+                    self.values_stack.push(value);
+                }
+                Instruction::CallNamed { var, name, argc } => {
+                    let chunk = frame.chunk.clone();
+                    let args = self.pop_values(argc)?;
+                    let value = match self.var_value(var) {
+                        Some(this_value) => match self.mut_method(this_value, &chunk.names[name]) {
+                            Some(f) => self.call_mut_method(f, var, &chunk.names[name], args)?,
+                            None => self.call_method(this_value, &chunk.names[name], args)?,
+                        },
+                        None => {
+                            let namespace = self.var_name(var).to_string();
+                            self.call_module(&namespace, &chunk.names[name], args)?
+                        }
+                    };
+                    self.values_stack.push(value);
+                }
+                Instruction::DefineFunction(i) => {
+                    let function = frame.chunk.functions[i].clone();
+                    self.user_funcs.insert(function.signature.name.clone(), function);
+                }
+                Instruction::Import { path, namespace } => {
+                    let chunk = frame.chunk.clone();
+                    let module = load_module(&self.module_loader, &chunk.names[path], self.script_dir.as_deref())?;
+                    self.modules.insert(chunk.names[namespace].clone(), module);
+                }
+                Instruction::CheckStack(n) => {
+                    let expected = frame.stack_len + n;
+                    if self.values_stack.len() != expected {
+                        return Err(format!("values stack leak: {} values after the statement, expected {}", self.values_stack.len(), expected));
+                    }
+                }
+                Instruction::Return => {
+                    let value = self.get_current_stack_value()?;
+                    let frame = self.frames.pop().unwrap();
+                    self.values_stack.truncate(frame.stack_len);
+                    if self.frames.len() == base {
+                        return Ok(Some(value));
+                    }
+                    self.values_stack.push(value);
+                }
+                Instruction::End => {
+                    let frame = self.frames.pop().unwrap();
+                    self.values_stack.truncate(frame.stack_len);
+                    return Ok(None);
                 }
             }
-        } else {
-            return Err("empty stack in unary expression".to_string()); 
         }
-        Ok(())          
-
     }
 
-    fn visit_binary_expression(&mut self, expr: &crate::ex_core::expressions::BinaryExpression) -> ExpressionVisitResult {
-        expr.left.accept(self)?;
-        expr.right.accept(self)?;
-        let op = expr.op.clone();
-        if let (Some(r), Some(l)) = (self.values_stack.pop(), self.values_stack.pop()) {
-            match (l, r) {
-                (ValueVariant::Float(l_float), ValueVariant::Float(r_float)) => {
-                    match op {
-                        Token::Plus => {
-                            self.values_stack.push(ValueVariant::Float(l_float + r_float));
-                        }
-                        Token::Minus => {
-                            self.values_stack.push(ValueVariant::Float(l_float - r_float));
-                        }
-                        Token::Multi => {
-                            self.values_stack.push(ValueVariant::Float(l_float * r_float));
-                        }
-                        Token::Devide => {
-                            self.values_stack.push(ValueVariant::Float(l_float / r_float));
-                        }
-                        Token::Eq => {
-                            self.values_stack.push(ValueVariant::Bool(l_float == r_float));
-                        }
-                        Token::NotEq => {
-                            self.values_stack.push(ValueVariant::Bool(l_float != r_float));
-                        }
-                        Token::More => {
-                            self.values_stack.push(ValueVariant::Bool(l_float > r_float));
-                        }
-                        Token::MoreEq => {
-                            self.values_stack.push(ValueVariant::Bool(l_float >= r_float));
-                        }
-                        Token::Less => {
-                            self.values_stack.push(ValueVariant::Bool(l_float < r_float));
-                        }
-                        Token::LessEq => {
-                            self.values_stack.push(ValueVariant::Bool(l_float <= r_float));
-                        }
-                        _ => {
-                            return Err(format!("binary op {} not supported for float's", op.to_string()));
-                        }
-                    }
+    /// applies binary operator to the values, used by binary expressions and compound assignments
+    fn binary_op(&self, op: BinaryOp, l: ValueVariant, r: ValueVariant) -> Result<ValueVariant, String> {
+        match (l, r) {
+            (l, r) if l == ValueVariant::Nil || r == ValueVariant::Nil => {
+                match op {
+                    BinaryOp::Eq => Ok(ValueVariant::Bool(l == r)),
+                    BinaryOp::NotEq => Ok(ValueVariant::Bool(l != r)),
+                    _ => Err(format!("binary op {} not supported for nil", op)),
                 }
-                (ValueVariant::Integer(l_int), ValueVariant::Integer(r_int)) => {
-                    match op {
-                        Token::Plus => {
-                            self.values_stack.push(ValueVariant::Integer(l_int + r_int));
-                        }
-                        Token::Minus => {
-                            self.values_stack.push(ValueVariant::Integer(l_int - r_int));
-                        }
-                        Token::Multi => {
-                            self.values_stack.push(ValueVariant::Integer(l_int * r_int));
-                        }
-                        Token::Devide => {
-                            self.values_stack.push(ValueVariant::Float(l_int as f64 / r_int as f64));
-                        }
-                        Token::Eq => {
-                            self.values_stack.push(ValueVariant::Bool(l_int == r_int));
-                        }
-                        Token::NotEq => {
-                            self.values_stack.push(ValueVariant::Bool(l_int != r_int));
-                        }
-                        Token::More => {
-                            self.values_stack.push(ValueVariant::Bool(l_int > r_int));
-                        }
-                        Token::MoreEq => {
-                            self.values_stack.push(ValueVariant::Bool(l_int >= r_int));
-                        }
-                        Token::Less => {
-                            self.values_stack.push(ValueVariant::Bool(l_int < r_int));
-                        }
-                        Token::LessEq => {
-                            self.values_stack.push(ValueVariant::Bool(l_int <= r_int));
-                        }
-                        _ => {
-                            return Err(format!("binary op {} not supported for float's", op.to_string()));
-                        }
+            }
+            (ValueVariant::Float(l_float), ValueVariant::Float(r_float)) => {
+                match op {
+                    BinaryOp::Plus => {
+                        Ok(ValueVariant::Float(l_float + r_float))
+                    }
+                    BinaryOp::Minus => {
+                        Ok(ValueVariant::Float(l_float - r_float))
+                    }
+                    BinaryOp::Multi => {
+                        Ok(ValueVariant::Float(l_float * r_float))
+                    }
+                    BinaryOp::Devide => {
+                        Ok(ValueVariant::Float(l_float / r_float))
+                    }
+                    BinaryOp::Pow => {
+                        Ok(ValueVariant::Float(l_float.powf(r_float)))
+                    }
+                    BinaryOp::Percent => {
+                        Ok(ValueVariant::Float(l_float % r_float))
+                    }
+                    BinaryOp::Eq => {
+                        Ok(ValueVariant::Bool(l_float == r_float))
+                    }
+                    BinaryOp::NotEq => {
+                        Ok(ValueVariant::Bool(l_float != r_float))
+                    }
+                    BinaryOp::More => {
+                        Ok(ValueVariant::Bool(l_float > r_float))
+                    }
+                    BinaryOp::MoreEq => {
+                        Ok(ValueVariant::Bool(l_float >= r_float))
+                    }
+                    BinaryOp::Less => {
+                        Ok(ValueVariant::Bool(l_float < r_float))
+                    }
+                    BinaryOp::LessEq => {
+                        Ok(ValueVariant::Bool(l_float <= r_float))
                     }
                 }
-                (ValueVariant::String(l_string), ValueVariant::String(r_string)) => {
-                    match op {
-                        Token::Plus => {
-                            self.values_stack.push(ValueVariant::String(l_string + &r_string));
+            }
+            (ValueVariant::Integer(l_int), ValueVariant::Integer(r_int)) => {
+                match op {
+                    BinaryOp::Plus | BinaryOp::Minus | BinaryOp::Multi => {
+                        let (result, symbol) = match op {
+                            BinaryOp::Plus => (l_int.checked_add(r_int), "+"),
+                            BinaryOp::Minus => (l_int.checked_sub(r_int), "-"),
+                            _ => (l_int.checked_mul(r_int), "*"),
+                        };
+                        match result {
+                            Some(i) => Ok(ValueVariant::Integer(i)),
+                            None => Err(format!("integer overflow in {} {} {}", l_int, symbol, r_int)),
                         }
-                        Token::Eq => {
-                            self.values_stack.push(ValueVariant::Bool(l_string == r_string));
+                    }
+                    BinaryOp::Devide => {
+                        Ok(ValueVariant::Float(l_int as f64 / r_int as f64))
+                    }
+                    BinaryOp::Percent => {
+                        if r_int == 0 {
+                            return Err(format!("division by zero in {} % {}", l_int, r_int));
                         }
-                        Token::NotEq => {
-                            self.values_stack.push(ValueVariant::Bool(l_string != r_string));
+                        // the minimal int % -1 overflows
+                        match l_int.checked_rem(r_int) {
+                            Some(i) => Ok(ValueVariant::Integer(i)),
+                            None => Err(format!("integer overflow in {} % {}", l_int, r_int)),
                         }
-                        _ => {
-                            return Err(format!("binary op {} not supported for strings", op.to_string()));
+                    }
+                    BinaryOp::Pow => {
+                        // negative power gives a fraction
+                        if r_int < 0 {
+                            Ok(ValueVariant::Float((l_int as f64).powf(r_int as f64)))
+                        } else {
+                            let result = u32::try_from(r_int).ok().and_then(|r| l_int.checked_pow(r));
+                            match result {
+                                Some(i) => Ok(ValueVariant::Integer(i)),
+                                None => Err(format!("integer overflow in {} ** {}", l_int, r_int)),
+                            }
                         }
                     }
+                    BinaryOp::Eq => {
+                        Ok(ValueVariant::Bool(l_int == r_int))
+                    }
+                    BinaryOp::NotEq => {
+                        Ok(ValueVariant::Bool(l_int != r_int))
+                    }
+                    BinaryOp::More => {
+                        Ok(ValueVariant::Bool(l_int > r_int))
+                    }
+                    BinaryOp::MoreEq => {
+                        Ok(ValueVariant::Bool(l_int >= r_int))
+                    }
+                    BinaryOp::Less => {
+                        Ok(ValueVariant::Bool(l_int < r_int))
+                    }
+                    BinaryOp::LessEq => {
+                        Ok(ValueVariant::Bool(l_int <= r_int))
+                    }
                 }
-                (ValueVariant::Bool(lb), ValueVariant::Bool(rb)) => {
-                    match op {
-                        Token::Eq => {
-                            self.values_stack.push(ValueVariant::Bool(lb == rb));
-                        }
-                        Token::NotEq => {
-                            self.values_stack.push(ValueVariant::Bool(lb != rb));
-                        }
-                        _ => {
-                            return Err(format!("binary op {} not supported for bool", op.to_string()));
-                        }
+            }
+            (ValueVariant::String(l_string), ValueVariant::String(r_string)) => {
+                match op {
+                    BinaryOp::Plus => {
+                        Ok(ValueVariant::String([&*l_string, &*r_string].concat().into()))
+                    }
+                    BinaryOp::Eq => {
+                        Ok(ValueVariant::Bool(l_string == r_string))
+                    }
+                    BinaryOp::NotEq => {
+                        Ok(ValueVariant::Bool(l_string != r_string))
+                    }
+                    _ => {
+                        Err(format!("binary op {} not supported for strings", op))
                     }
                 }
-
-                
-                _ => {
-                    return Err(format!("for now binary operation {} for this args", op.to_string()));
+            }
+            (ValueVariant::String(l_string), ValueVariant::Char(r_char)) if op == BinaryOp::Plus => {
+                let mut result = l_string.to_string();
+                result.push(r_char);
+                Ok(ValueVariant::String(result.into()))
+            }
+            (ValueVariant::Char(l_char), ValueVariant::String(r_string)) if op == BinaryOp::Plus => {
+                Ok(ValueVariant::String(format!("{}{}", l_char, r_string).into()))
+            }
+            (ValueVariant::Char(c), ValueVariant::String(s)) | (ValueVariant::String(s), ValueVariant::Char(c)) => {
+                match op {
+                    BinaryOp::Eq => Ok(ValueVariant::Bool(is_char_string(c, &s))),
+                    BinaryOp::NotEq => Ok(ValueVariant::Bool(!is_char_string(c, &s))),
+                    _ => Err(format!("binary op {} not supported for char and string", op)),
                 }
             }
-        } else {
-            return Err("empty stack in bynary expression".to_string());
-        }
-        Ok(())
-    }
-
-    fn visit_assignment_expression(&mut self, expr: &crate::ex_core::expressions::AssignmentExpression) -> ExpressionVisitResult {
-        assert!(self.var_scopes.len() >= 1);
-        expr.value.accept(self)?;
-        if let Some(value) = self.values_stack.pop() {
-            let len = self.var_scopes.len(); 
-            self.var_scopes[len - 1].insert(expr.name.clone(), value);
-            Ok(())
-        } else {
-            Err(String::from("no value for assgignment expression"))
-        }
-    }
-
-    fn visit_if_expression(&mut self, expr: &super::IfExpression) ->ExpressionVisitResult {
-        expr.if_expr.accept(self)?;
-        if let Some(value) = self.values_stack.pop() {
-            match value {
-                ValueVariant::Bool(b) => {
-                    if b {
-                        for expr in expr.true_expression.iter() {
-                            expr.accept(self)?;
-                        } 
-                    } else {
-                        for expr in expr.false_expression.iter() {
-                            expr.accept(self)?;
-                        }   
-                    }
+            (ValueVariant::Char(l_char), ValueVariant::Char(r_char)) => {
+                match op {
+                    BinaryOp::Plus => Ok(ValueVariant::String(format!("{}{}", l_char, r_char).into())),
+                    BinaryOp::Eq => Ok(ValueVariant::Bool(l_char == r_char)),
+                    BinaryOp::NotEq => Ok(ValueVariant::Bool(l_char != r_char)),
+                    BinaryOp::More => Ok(ValueVariant::Bool(l_char > r_char)),
+                    BinaryOp::MoreEq => Ok(ValueVariant::Bool(l_char >= r_char)),
+                    BinaryOp::Less => Ok(ValueVariant::Bool(l_char < r_char)),
+                    BinaryOp::LessEq => Ok(ValueVariant::Bool(l_char <= r_char)),
+                    _ => Err(format!("binary op {} not supported for chars", op)),
                 }
-                _ => {
-                    return Err("not expected result in if".to_string());
+            }
+            (ValueVariant::Bytes(l_bytes), ValueVariant::Bytes(r_bytes)) => {
+                match op {
+                    BinaryOp::Plus => Ok(ValueVariant::Bytes(Rc::new([&l_bytes[..], &r_bytes[..]].concat()))),
+                    BinaryOp::Eq => Ok(ValueVariant::Bool(l_bytes == r_bytes)),
+                    BinaryOp::NotEq => Ok(ValueVariant::Bool(l_bytes != r_bytes)),
+                    _ => Err(format!("binary op {} not supported for bytes", op)),
                 }
             }
-        }
-        Ok(())
-    }
-
-    
-    fn visit_while_expression(&mut self, expr: &super::WhileExpression) -> ExpressionVisitResult {
-        loop {
-            expr.while_expr.accept(self)?;
-            if let Some(value) = self.values_stack.pop() {
-                match value {
-                    ValueVariant::Bool(b) => {
-                        if b {
-                            for expr in expr.body_exprs.iter() {
-                                expr.accept(self)?;
-                            } 
-                        } else {
-                            break;
-                        }
+            (ValueVariant::Bool(lb), ValueVariant::Bool(rb)) => {
+                match op {
+                    BinaryOp::Eq => {
+                        Ok(ValueVariant::Bool(lb == rb))
+                    }
+                    BinaryOp::NotEq => {
+                        Ok(ValueVariant::Bool(lb != rb))
                     }
                     _ => {
-                        return Err("not expected result in while".to_string());
+                        Err(format!("binary op {} not supported for bool", op))
                     }
                 }
             }
-        }
-        Ok(())
-    }
 
-    fn visit_for_expression(&mut self, expr: &super::ForExpression) -> ExpressionVisitResult {
-        expr.l_bound.accept(self)?;
-        expr.r_bound.accept(self)?;
-        let r_bound = self.get_current_stack_value()?;
-        let l_bound = self.get_current_stack_value()?;
-        let mut l_bound_i = self.value_variant_to_int(&l_bound)?;
-        let r_bound_i = self.value_variant_to_int(&r_bound)?;
-        self.add_var(&expr.var_name, &l_bound);
-        while l_bound_i < r_bound_i {
-            for e in expr.body_exprs.iter() {
-                e.accept(self)?;
+            
+            _ => {
+                Err(format!("for now binary operation {} for this args", op))
             }
-            self.add_var(&expr.var_name, &ValueVariant::Integer(l_bound_i + 1));
-            let i_value = self._get_var_value(&expr.var_name).unwrap();
-            l_bound_i = self.value_variant_to_int(&i_value)?;
         }
-        Ok(())
     }
 
-    fn visit_function_def_expression(&mut self, expr: &crate::ex_core::expressions::FunctionDefExpression) -> ExpressionVisitResult {
-        self.user_funcs.insert(expr.name.clone(), expr.clone());
-        return Ok(());
-    }
-
-    fn visit_function_call_expression(&mut self,  expr: &crate::ex_core::expressions::FunctionCallExpression) -> ExpressionVisitResult {
-        if self.std_funcs.contains_key(&expr.name) {
-            return self.call_std_func(expr);
-        } else if self.user_funcs.contains_key(&expr.name) {
-            return self.call_user_func(expr);
-        } else {
-            return Err(format!("function {} not defined", &expr.name));
-        }
-    }
-
-    fn visit_method_call_expression(&mut self, expr: &super::MethodCallExpression) -> ExpressionVisitResult {
-        if let Some(this_value) = self._get_var(&expr.self_name).cloned() {
-            return self.call_method(&this_value, &expr.method_name, &expr.args);
-        } else {
-            return Err(format!("call method with unknown object '{}'", expr.self_name));
-        }
-    }
+}
 
-    fn visit_anonymous_method_call_expression(&mut self, expr: &super::AnonymousMethodExpression) -> ExpressionVisitResult {
-        expr.self_expr.accept(self)?;
-        if let Some(this_value) = self.values_stack.pop() {
-            return self.call_method(&this_value, &expr.method_name, &expr.args);
-        } else {
-            return Err(format!("no value to call method {}", expr.method_name));
-        }
-    }
 
-    fn visit_return_expression(&mut self, expr: &crate::ex_core::expressions::ReturnExpression) -> ExpressionVisitResult {
-        return expr.expr.accept(self);
-    }
-
-    fn visit_statement_list_expression(&mut self, expr: &crate::ex_core::expressions::StatementListExpression) -> ExpressionVisitResult {
-        for statement in expr.statement_list.iter() {
-            statement.accept(self)?;
-        }
-        Ok(())
-    }
-
-}
 
 
 mod tests {
@@ -551,15 +1225,15 @@ mod tests {
         test_map.insert("a  = 2 + 2 * 2 * 2", super::ValueVariant::Integer(10));
         test_map.insert("b  = 0 - 3 \n\
                            a = b - 1", super::ValueVariant::Integer(-4));
-        test_map.insert("a  = \"aa\" + \"bb\"", super::ValueVariant::String(String::from("aabb")));
+        test_map.insert("a  = \"aa\" + \"bb\"", super::ValueVariant::String("aabb".into()));
         test_map.insert("b  = \"aa\" \n\
-                           a = b + \"bb\" ", super::ValueVariant::String(String::from("aabb")));
+                           a = b + \"bb\" ", super::ValueVariant::String("aabb".into()));
         for (prog, exp_res) in test_map.iter() {
             let prog = prog.to_string();
-            let expr = crate::ex_core::parser::Parser::new(&crate::ex_core::tokenize(&prog)).parse().unwrap();
+            let expr = crate::ex_core::parser::Parser::new(&crate::ex_core::tokenize(&prog).unwrap()).parse().unwrap();
             let mut interp = crate::ex_core::interp::Interpreter::new(); 
             interp.interp_expr(expr).unwrap();
-            assert_eq!(interp._get_var_value(&"a".to_string()).unwrap(), *exp_res);
+            assert_eq!(interp._get_var_value("a").unwrap(), *exp_res);
         }
     }
 
@@ -570,7 +1244,7 @@ mod tests {
                                 return \"afs\" \n\
                             }\n\
                             test()".to_string();    
-        let tokens = crate::ex_core::tokenize(&prog);
+        let tokens = crate::ex_core::tokenize(&prog).unwrap();
         let expr = crate::ex_core::parser::Parser::new(&tokens).parse().unwrap();
         let mut interp = crate::ex_core::interp::Interpreter::new(); 
         interp.interp_expr(expr).unwrap();
@@ -585,7 +1259,7 @@ mod tests {
                             test(123)".to_string();    
 
                             
-        let tokens = crate::ex_core::tokenize(&prog);
+        let tokens = crate::ex_core::tokenize(&prog).unwrap();
         let expr = crate::ex_core::parser::Parser::new(&tokens).parse().unwrap();
         let mut interp = crate::ex_core::interp::Interpreter::new(); 
         interp.interp_expr(expr).unwrap();
@@ -599,7 +1273,7 @@ mod tests {
         let prog : String = "if (true) { \n\
                                 writeln(\"bububu\") \n\
                             }\n".to_string();                                
-        let tokens = crate::ex_core::tokenize(&prog);
+        let tokens = crate::ex_core::tokenize(&prog).unwrap();
         let expr = crate::ex_core::parser::Parser::new(&tokens).parse().unwrap();
         let mut interp = crate::ex_core::interp::Interpreter::new(); 
         interp.interp_expr(expr).unwrap();
@@ -611,11 +1285,678 @@ mod tests {
         let prog : String = "for i in [0, 10] { \n\
             writeln(\"fuuu\") \n\
         }\n".to_string();                                
-        let tokens = crate::ex_core::tokenize(&prog);
+        let tokens = crate::ex_core::tokenize(&prog).unwrap();
         let expr = crate::ex_core::parser::Parser::new(&tokens).parse().unwrap();
         let mut interp = crate::ex_core::interp::Interpreter::new(); 
         interp.interp_expr(expr).unwrap();
         assert_eq!(true, true);
     }
-        
+
+    #[test]
+    fn env_test() {
+        let prog : String = "set_env(\"EX_INTERP_ENV_TEST\", 42) \n\
+                             a = env(\"EX_INTERP_ENV_TEST\") \n\
+                             unset_env(\"EX_INTERP_ENV_TEST\") \n\
+                             b = env(\"EX_INTERP_ENV_TEST\", \"unset\")".to_string();
+        let tokens = crate::ex_core::tokenize(&prog).unwrap();
+        let expr = crate::ex_core::parser::Parser::new(&tokens).parse().unwrap();
+        let mut interp = crate::ex_core::interp::Interpreter::new();
+        interp.interp_expr(expr).unwrap();
+        assert_eq!(interp._get_var_value("a").unwrap(), super::ValueVariant::String("42".into()));
+        assert_eq!(interp._get_var_value("b").unwrap(), super::ValueVariant::String("unset".into()));
+    }
+
+    #[test]
+    fn script_args_test() {
+        let prog : String = "n = args.len() \n\
+                             a = args.get(1)".to_string();
+        let tokens = crate::ex_core::tokenize(&prog).unwrap();
+        let expr = crate::ex_core::parser::Parser::new(&tokens).parse().unwrap();
+        let mut interp = crate::ex_core::interp::Interpreter::new();
+        let args = super::ValueVariant::List(vec![super::ValueVariant::String("x".into()),
+                                                  super::ValueVariant::String("y".into())].into());
+        interp.add_var("args", &args);
+        interp.interp_expr(expr).unwrap();
+        assert_eq!(interp._get_var_value("n").unwrap(), super::ValueVariant::Integer(2));
+        assert_eq!(interp._get_var_value("a").unwrap(), super::ValueVariant::String("y".into()));
+        assert_eq!(args.to_string(), "[\"x\", \"y\"]");
+    }
+
+    #[cfg(test)]
+    fn run_prog(prog: &str) -> crate::ex_core::interp::Interpreter {
+        let tokens = crate::ex_core::tokenize(prog).unwrap();
+        let expr = crate::ex_core::parser::Parser::new(&tokens).parse().unwrap();
+        let mut interp = crate::ex_core::interp::Interpreter::new();
+        interp.interp_expr(expr).unwrap();
+        interp
+    }
+
+    #[test]
+    fn list_and_map_literals_test() {
+        let mut interp = run_prog("l = [1, \"a\", [2.5, true]] \n\
+                                   m = {\"timeout\": 5, name: \"x\"}\n\
+                                   n = m.get(\"name\")");
+        assert_eq!(interp._get_var_value("l").unwrap().to_string(), "[1, \"a\", [2.5, true]]");
+        assert_eq!(interp._get_var_value("m").unwrap().to_string(), "{\"name\": \"x\", \"timeout\": 5}");
+        assert_eq!(interp._get_var_value("n").unwrap(), super::ValueVariant::String("x".into()));
+    }
+
+    #[test]
+    fn operator_precedence_test() {
+        let tests = [
+            ("2 + 3 * 4", super::ValueVariant::Integer(14)),
+            ("(2 + 3) * 4", super::ValueVariant::Integer(20)),
+            ("10 - 4 - 3", super::ValueVariant::Integer(3)),
+            ("12.0 / 3.0 / 2.0", super::ValueVariant::Float(2.0)),
+            ("-2 * 3", super::ValueVariant::Integer(-6)),
+            ("- -2", super::ValueVariant::Integer(2)),
+            ("2 ** 3 ** 2", super::ValueVariant::Integer(512)),
+            ("-2 ** 2", super::ValueVariant::Integer(-4)),
+            ("2 ** -1", super::ValueVariant::Float(0.5)),
+            ("1 + 2 == 3", super::ValueVariant::Bool(true)),
+            ("1 < 2 == 2 < 1", super::ValueVariant::Bool(false)),
+            ("1 + 2 * 3 >= 7 != false", super::ValueVariant::Bool(true)),
+            ("[1, 2].len() * 2", super::ValueVariant::Integer(4)),
+            ("-[1, 2].len()", super::ValueVariant::Integer(-2)),
+            ("s.len() + s.len() ** 2", super::ValueVariant::Integer(6)),
+            ("1 + f(2) * 2", super::ValueVariant::Integer(9)),
+            ("f(1) == 2", super::ValueVariant::Bool(true)),
+        ];
+        for (expr, expected) in tests {
+            let prog = format!("s = [1, 2] \n fn f(x) {{ return x * 2 }} \n a = {}", expr);
+            let mut interp = run_prog(&prog);
+            assert_eq!(interp._get_var_value("a").unwrap(), expected, "expression: {}", expr);
+        }
+    }
+
+    #[test]
+    fn integer_overflow_test() {
+        let tests = [
+            ("x % 0", "division by zero in 1 % 0"),
+            ("m % -1", "integer overflow in -9223372036854775808 % -1"),
+            ("2 ** 63", "integer overflow in 2 ** 63"),
+            ("9223372036854775807 + 1", "integer overflow in 9223372036854775807 + 1"),
+            ("m - 1", "integer overflow in -9223372036854775808 - 1"),
+            ("m * -1", "integer overflow in -9223372036854775808 * -1"),
+            ("4611686018427387904 * 2", "integer overflow in 4611686018427387904 * 2"),
+            ("-m", "integer overflow in --9223372036854775808"),
+        ];
+        for (expr, err) in tests {
+            let prog = format!("x = 1 \n m = -9223372036854775807 - 1 \n a = {}", expr);
+            let expr = crate::ex_core::parser::Parser::new(&crate::ex_core::tokenize(&prog).unwrap()).parse().unwrap();
+            let mut interp = crate::ex_core::interp::Interpreter::new();
+            assert_eq!(interp.interp_expr(expr).unwrap_err(), err);
+        }
+        let mut interp = run_prog("m = -9223372036854775807 - 1 \n a = m % 2");
+        assert_eq!(interp._get_var_value("a").unwrap(), super::ValueVariant::Integer(0));
+    }
+
+    #[test]
+    fn assignment_test() {
+        let mut interp = run_prog("a = 10 \n\
+                                   a += 5 \n\
+                                   a -= 1 \n\
+                                   a *= 2 \n\
+                                   a %= 5 \n\
+                                   f = 3.0 \n\
+                                   f /= 2.0 \n\
+                                   x, y = 1, 2 \n\
+                                   x, y = y, x \n\
+                                   pair = [3, [4, 5]] \n\
+                                   [p, [q, r]] = pair \n\
+                                   s, t = pair \n\
+                                   person = {name: \"ann\", age: 30} \n\
+                                   {name, age} = person \n\
+                                   xs = [1, 2, 3] \n\
+                                   xs[0] = 10 \n\
+                                   xs[1] += 5 \n\
+                                   person.age += 1 \n\
+                                   person.city = \"paris\" \n\
+                                   person[\"tags\"] = [\"a\"] \n\
+                                   person.tags[0] = \"b\" \n\
+                                   first = xs[0] + pair[1][1] \n\
+                                   city = person.city");
+        let mut var = |name: &str| interp._get_var_value(name).unwrap().to_string();
+        assert_eq!(var("a"), "3");
+        assert_eq!(var("f"), "1.5");
+        assert_eq!((var("x"), var("y")), ("2".to_string(), "1".to_string()));
+        assert_eq!((var("p"), var("q"), var("r")), ("3".to_string(), "4".to_string(), "5".to_string()));
+        assert_eq!((var("s"), var("t")), ("3".to_string(), "[4, 5]".to_string()));
+        assert_eq!((var("name"), var("age")), ("ann".to_string(), "30".to_string()));
+        assert_eq!(var("xs"), "[10, 7, 3]");
+        assert_eq!(var("person"), "{\"age\": 31, \"city\": \"paris\", \"name\": \"ann\", \"tags\": [\"b\"]}");
+        assert_eq!(var("first"), "15");
+        assert_eq!(var("city"), "paris");
+    }
+
+    #[test]
+    fn assignment_error_test() {
+        let tests = [
+            ("xs = [1] \n xs[1] = 2", "index 1 is out of range for list of length 1"),
+            ("[a, b] = [1]", "can't destructure list of 1 items to 2 targets"),
+            ("{a} = {b: 1}", "no key 'a' in map to destructure"),
+            ("a, b = 1, 2, 3", "can't assign 3 values to 2 targets"),
+            ("a, b = 1", "can't destructure int value to 2 targets"),
+            ("a += 1", "unknown name 'a'"),
+            ("a = 1 \n a.x = 2", "can't index int value with string value"),
+        ];
+        for (prog, expected) in tests {
+            let expr = crate::ex_core::parser::Parser::new(&crate::ex_core::tokenize(prog).unwrap()).parse().unwrap();
+            let mut interp = crate::ex_core::interp::Interpreter::new();
+            assert_eq!(interp.interp_expr(expr).unwrap_err(), expected);
+        }
+    }
+
+    #[test]
+    fn else_if_test() {
+        let prog = "fn sign(x) {\n\
+                        if x < 0 {\n\
+                            s = \"negative\"\n\
+                        } else if x == 0 {\n\
+                            s = \"zero\"\n\
+                        } else if x < 10 {\n\
+                            s = \"small\"\n\
+                        } else {\n\
+                            s = \"big\"\n\
+                        }\n\
+                        return s\n\
+                    }\n\
+                    a = [sign(-1), sign(0), sign(5), sign(50)]";
+        let mut interp = run_prog(prog);
+        assert_eq!(interp._get_var_value("a").unwrap().to_string(), "[\"negative\", \"zero\", \"small\", \"big\"]");
+    }
+
+    #[test]
+    fn match_test() {
+        let prog = "fn describe(v) {\n\
+                        match v {\n\
+                            0 => r = \"zero\"\n\
+                            \"a\" | \"b\" => r = \"letter\"\n\
+                            1..10 => r = \"digit\"\n\
+                            10..=20 => {\n\
+                                r = \"teen\"\n\
+                            }\n\
+                            -5..0 | 0.5 => r = \"odd\"\n\
+                            true => r = \"bool\"\n\
+                            n if n > 100 => r = n * 2\n\
+                            _ => r = \"other\"\n\
+                        }\n\
+                        return r\n\
+                    }\n\
+                    a = [describe(0), describe(\"b\"), describe(9), describe(20), describe(-3), describe(0.5), describe(101), describe(true), describe(50)]";
+        let mut interp = run_prog(prog);
+        assert_eq!(interp._get_var_value("a").unwrap().to_string(),
+                   "[\"zero\", \"letter\", \"digit\", \"teen\", \"odd\", \"odd\", 202, \"bool\", \"other\"]");
+
+        let expr = crate::ex_core::parser::Parser::new(&crate::ex_core::tokenize("match 3 {\n 1 | 2 => a = 1\n}").unwrap()).parse().unwrap();
+        let mut interp = crate::ex_core::interp::Interpreter::new();
+        assert_eq!(interp.interp_expr(expr).unwrap_err(), "non-exhaustive match: no arm matches value 3");
+    }
+
+    #[test]
+    fn nil_and_return_test() {
+        let mut interp = run_prog("fn noop() {\n x = 1\n }\n\
+                                   fn early(x) {\n if x > 0 {\n return \"pos\"\n }\n return\n }\n\
+                                   fn first_over(n) {\n for i in [0, 10] {\n if i * i > n {\n return i\n }\n }\n return nil\n }\n\
+                                   fn count_to(n) {\n i = 0\n while true {\n i += 1\n if i == n {\n return i\n }\n }\n }\n\
+                                   n = 3 \n\
+                                   a = noop() \n\
+                                   b = [a == nil, a != nil, 1 == nil, nil == nil] \n\
+                                   c = [early(1), early(-1), first_over(10), first_over(1000), count_to(n)] \n\
+                                   d = early(-1) ?? \"default\" \n\
+                                   e = 5 ?? undefined_func() \n\
+                                   f = nil ?? nil ?? 3 \n\
+                                   g = write() \n\
+                                   h = [1, nil]");
+        let mut var = |name: &str| interp._get_var_value(name).unwrap().to_string();
+        assert_eq!(var("a"), "nil");
+        assert_eq!(var("b"), "[true, false, false, true]");
+        assert_eq!(var("c"), "[\"pos\", nil, 4, nil, 3]");
+        assert_eq!(var("d"), "default");
+        assert_eq!(var("e"), "5");
+        assert_eq!(var("f"), "3");
+        assert_eq!(var("g"), "nil");
+        assert_eq!(var("h"), "[1, nil]");
+    }
+
+    #[test]
+    fn function_params_test() {
+        let mut interp = run_prog("fn f(a, b = a * 2, c = 10) {\n return [a, b, c]\n }\n\
+                                   fn sum(first, ...rest) {\n s = first\n for i in [0, rest.len()] {\n s += rest[i]\n }\n return s\n }\n\
+                                   a = [f(1), f(1, 5), f(1, 5, 7)] \n\
+                                   b = [f(c: 3, a: 1), f(2, c: 0)] \n\
+                                   c = [sum(1), sum(1, 2, 3)] \n\
+                                   d = [json_stringify([1], pretty: false), json_stringify(pretty: false, value: [2])] \n\
+                                   e = env(\"EX_KEYWORD_TEST_UNSET\", default: 1)");
+        let mut var = |name: &str| interp._get_var_value(name).unwrap().to_string();
+        assert_eq!(var("a"), "[[1, 2, 10], [1, 5, 10], [1, 5, 7]]");
+        assert_eq!(var("b"), "[[1, 2, 3], [2, 4, 0]]");
+        assert_eq!(var("c"), "[1, 6]");
+        // std functions get keyword arguments by names of their signatures
+        assert_eq!(var("d"), "[\"[1]\", \"[2]\"]");
+        assert_eq!(var("e"), "1");
+    }
+
+    #[test]
+    fn function_arity_error_test() {
+        let defs = "fn f(a, b) {\n}\nfn g(a, b = 1) {\n}\nfn h(a, ...rest) {\n}\n";
+        let tests = [
+            ("f(1)", "function f expects 2 arguments, got 1"),
+            ("f(1, 2, 3)", "function f expects 2 arguments, got 3"),
+            ("g(1, 2, 3)", "function g expects 1 to 2 arguments, got 3"),
+            ("h()", "function h expects at least 1 argument, got 0"),
+            ("f(b: 1)", "missing argument 'a' of function f"),
+            ("f(1, a: 2)", "argument 'a' of function f is given twice"),
+            ("f(1, b: 2, b: 3)", "argument 'b' of function f is given twice"),
+            ("f(1, c: 2)", "function f has no argument 'c'"),
+            ("h(1, rest: [])", "function h has no argument 'rest'"),
+            ("writeln(s: 1)", "function writeln has no argument 's'"),
+            ("writeln(values: 1)", "function writeln has no argument 'values'"),
+            ("env(default: 1)", "missing argument 'name' of function env"),
+            ("env(\"HOME\", name: \"x\")", "argument 'name' of function env is given twice"),
+            ("fn k(a, ...a) {\n}", "duplicate argument 'a' of function k"),
+        ];
+        for (prog, expected) in tests {
+            let prog = format!("{}{}", defs, prog);
+            let expr = crate::ex_core::parser::Parser::new(&crate::ex_core::tokenize(&prog).unwrap()).parse().unwrap();
+            let mut interp = crate::ex_core::interp::Interpreter::new();
+            assert_eq!(interp.interp_expr(expr).unwrap_err(), expected);
+        }
+        assert_eq!(run_prog(defs).get_user_func_signatures(), ["f(a, b)", "g(a, [b])", "h(a, ...rest)"]);
+    }
+
+    #[cfg(test)]
+    fn run_script(dir: &std::path::Path, name: &str, code: &str) -> Result<crate::ex_core::interp::Interpreter, String> {
+        let path = dir.join(name);
+        std::fs::write(&path, code).unwrap();
+        let expr = crate::ex_core::parser::Parser::new(&crate::ex_core::tokenize(code).unwrap()).parse().unwrap();
+        let mut interp = crate::ex_core::interp::Interpreter::new();
+        interp.set_script_path(&path);
+        interp.interp_expr(expr).map(|_| interp)
+    }
+
+    #[test]
+    fn import_test() {
+        let dir = std::env::temp_dir().join(format!("ex_import_test_{}", std::process::id()));
+        let ex_path_dir = dir.join("ex_path");
+        std::fs::create_dir_all(dir.join("lib")).unwrap();
+        std::fs::create_dir_all(&ex_path_dir).unwrap();
+        std::fs::write(dir.join("lib/utils.ex"), "import counter
+\
+                                                  version = \"1.0\"\n\
+                                                  fn twice(x) {\n return helper(x) * 2\n }\n\
+                                                  fn helper(x) {\n return x + counter.next()\n }\n").unwrap();
+        std::fs::write(dir.join("lib/counter.ex"), "writeln(\"loading counter\")\n\
+                                                    fn next() {\n return 0\n }\n").unwrap();
+        std::fs::write(ex_path_dir.join("strings.ex"), "import \"counter.ex\" as c\n\
+                                                        fn shout(s) {\n return s + \"!\"\n }\n").unwrap();
+        std::fs::write(ex_path_dir.join("counter.ex"), "fn next() {\n return 100\n }\n").unwrap();
+        std::env::set_var("EX_PATH", &ex_path_dir);
+
+        let mut interp = run_script(&dir, "main.ex", "import \"lib/utils.ex\"\n\
+                                                      import strings as s\n\
+                                                      import \"lib/utils.ex\" as u\n\
+                                                      fn f() {\n return u.twice(1)\n }\n\
+                                                      a = [utils.twice(3), f(), s.shout(\"hi\"), utils.version, u.version]").unwrap();
+        assert_eq!(interp._get_var_value("a").unwrap().to_string(), "[6, 2, \"hi!\", \"1.0\", \"1.0\"]");
+        // the module is loaded once and shared by its namespaces
+        assert!(std::rc::Rc::ptr_eq(&interp.modules["utils"], &interp.modules["u"]));
+
+        let err = run_script(&dir, "main.ex", "import utils").err().unwrap();
+        assert_eq!(err, "can't find module 'utils.ex'");
+        let err = run_script(&dir, "main.ex", "import \"lib/utils.ex\"\n utils.missing()").err().unwrap();
+        assert_eq!(err, "Error with function utils.missing : function missing not defined in module");
+
+        std::fs::write(dir.join("a.ex"), "import b\n").unwrap();
+        std::fs::write(dir.join("b.ex"), "import main\n").unwrap();
+        let err = run_script(&dir, "main.ex", "import a").err().unwrap();
+        assert!(err.contains("circular import: "), "{}", err);
+        assert!(err.ends_with(&format!("main.ex -> {0}a.ex -> {0}b.ex -> {0}main.ex", dir.canonicalize().unwrap().join("").display())), "{}", err);
+        std::env::remove_var("EX_PATH");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn runtime_error_recovery_test() {
+        let mut interp = run_prog("fn fail(x) {\n for i in [0, 3] {\n y = x + \"s\"\n }\n }\n\
+                                   fn ok(x) {\n return x\n }");
+        let expr = crate::ex_core::parser::Parser::new(&crate::ex_core::tokenize("a = ok(fail(1))").unwrap()).parse().unwrap();
+        assert_eq!(interp.interp_expr(expr).unwrap_err(), "for now binary operation PLUS TOKEN for this args\n\
+                                                           traceback (most recent call last):\n  \
+                                                           top level code, line 1: call of fail\n  \
+                                                           function fail");
+        // frames and values of the failed call are dropped
+        assert!(interp.frames.is_empty());
+        assert!(interp.values_stack.is_empty());
+        let expr = crate::ex_core::parser::Parser::new(&crate::ex_core::tokenize("a = ok(2)").unwrap()).parse().unwrap();
+        interp.interp_expr(expr).unwrap();
+        assert_eq!(interp._get_var_value("a").unwrap(), super::ValueVariant::Integer(2));
+    }
+
+    #[test]
+    fn values_stack_balance_test() {
+        // results of expression statements are dropped in loops, match arms, functions and at the top level
+        let mut interp = run_prog("fn f(x) {\n x.pow(2)\n for i in [0, 3] {\n match i {\n 0 => i.pow(2)\n _ => {\n [i, x]\n return x\n }\n }\n }\n }\n\
+                                   a = 2 \n\
+                                   a.pow(2) \n\
+                                   for i in [0, 1000] {\n a + i\n f(i)\n if i > 0 {\n \"s\"\n }\n }\n\
+                                   return 5");
+        assert!(interp.values_stack.is_empty());
+        assert!(interp.frames.is_empty());
+
+        // the value, that is left by the statement, is reported
+        let mut chunk = super::Chunk::default();
+        chunk.constants.push(super::ValueVariant::Integer(1));
+        chunk.code = vec![super::Instruction::Constant(0), super::Instruction::CheckStack(0), super::Instruction::End];
+        let err = interp.run_frame(None, std::rc::Rc::new(chunk), Vec::new()).unwrap_err();
+        assert_eq!(err, "values stack leak: 1 values after the statement, expected 0");
+        assert!(interp.values_stack.is_empty());
+    }
+
+    #[test]
+    fn top_level_return_test() {
+        // return outside of functions evaluates the value and doesn't stop the program
+        let mut interp = run_prog("fn f() {\n return 1\n }\n\
+                                   return f() + 1 \n\
+                                   b = 2");
+        assert_eq!(interp._get_var_value("b").unwrap(), super::ValueVariant::Integer(2));
+        assert!(interp.values_stack.is_empty());
+    }
+
+    #[test]
+    fn call_depth_test() {
+        let mut interp = run_prog("fn down(n) {\n return 1 + down(n + 1)\n }\n\
+                                   fn count(n) {\n if n == 0 {\n return 0\n }\n return count(n - 1) + 1\n }");
+        let mut run = |prog: &str| {
+            let expr = crate::ex_core::parser::Parser::new(&crate::ex_core::tokenize(prog).unwrap()).parse().unwrap();
+            interp.interp_expr(expr)
+        };
+        assert_eq!(run("\n down(1)").unwrap_err(), format!("maximum call depth {} exceeded\n\
+                                                            traceback (most recent call last):\n  \
+                                                            top level code, line 2: call of down\n  \
+                                                            function down, line 2: call of down\n  \
+                                                            ... repeated {} more times", super::DEFAULT_MAX_CALL_DEPTH, super::DEFAULT_MAX_CALL_DEPTH - 1));
+        // the interpreter works after the error and calls up to the maximum depth succeed
+        run(&format!("c = count({})", super::DEFAULT_MAX_CALL_DEPTH - 1)).unwrap();
+        assert_eq!(run("x = 1 + 2").map(|_| ()), Ok(()));
+        assert!(interp.frames.is_empty());
+    }
+
+    #[test]
+    fn try_call_test() {
+        let mut interp = run_prog("fn down(n) {\n return 1 + down(n + 1)\n }\n\
+                                   fn rest(n) {\n return 10 % n\n }\n\
+                                   fn safe_down() {\n r = try_call(\"down\", 1)\n return r[1]\n }\n\
+                                   a = try_call(\"rest\", 4) \n\
+                                   b = safe_down() \n\
+                                   c = try_call(\"rest\", 0)[1] \n\
+                                   d = try_call(\"rest\") \n\
+                                   after = 1");
+        let mut var = |name: &str| interp._get_var_value(name).unwrap().to_string();
+        assert_eq!(var("a"), "[2, nil]");
+        // the script recovers from exceeding the maximum call depth
+        assert_eq!(var("b"), format!("maximum call depth {} exceeded", super::DEFAULT_MAX_CALL_DEPTH));
+        assert_eq!(var("c"), "division by zero in 10 % 0");
+        assert_eq!(var("d"), "[nil, \"function rest expects 1 argument, got 0\"]");
+        assert_eq!(var("after"), "1");
+        assert!(interp.frames.is_empty() && interp.values_stack.is_empty());
+
+        let expr = crate::ex_core::parser::Parser::new(&crate::ex_core::tokenize("try_call(\"nope\")").unwrap()).parse().unwrap();
+        assert_eq!(interp.interp_expr(expr).unwrap_err(), "function nope not defined");
+    }
+
+    #[test]
+    fn tail_call_test() {
+        let mut interp = run_prog("fn count(n, acc = 0) {\n if n == 0 {\n return acc\n }\n return count(n - 1, acc: acc + 1)\n }\n\
+                                   fn find(xs, x, i) {\n for j in [0, 1] {\n if xs[i] == x {\n return i\n }\n return find(xs, x, i + 1)\n }\n }\n\
+                                   a = count(1000000) \n\
+                                   b = find([1, 2, 3], 3, 0)");
+        assert_eq!(interp._get_var_value("a").unwrap(), super::ValueVariant::Integer(1000000));
+        assert_eq!(interp._get_var_value("b").unwrap(), super::ValueVariant::Integer(2));
+        // values of the loop are dropped with the reused frame
+        assert!(interp.values_stack.is_empty());
+    }
+
+    #[test]
+    fn value_sharing_test() {
+        let mut interp = run_prog("fn set_first(xs) {\n xs[0] = 0\n return xs\n }\n\
+                               a = [1, [2, 3]] \n\
+                               b = a \n\
+                               c = a \n\
+                               b[1][0] = 5 \n\
+                               m = {x: 1} \n\
+                               n = m \n\
+                               n.x = 2 \n\
+                               d = set_first(a)");
+        let mut var = |name: &str| interp._get_var_value(name).unwrap();
+        // a change of the shared value is visible only through the changed variable
+        assert_eq!(var("a").to_string(), "[1, [2, 3]]");
+        assert_eq!(var("b").to_string(), "[1, [5, 3]]");
+        assert_eq!(var("d").to_string(), "[0, [2, 3]]");
+        assert_eq!(var("m").to_string(), "{\"x\": 1}");
+        assert_eq!(var("n").to_string(), "{\"x\": 2}");
+        // unchanged values are not copied
+        match (var("a"), var("c"), var("d")) {
+            (super::ValueVariant::List(a), super::ValueVariant::List(c), super::ValueVariant::List(d)) => {
+                assert!(std::rc::Rc::ptr_eq(&a, &c));
+                assert!(!std::rc::Rc::ptr_eq(&a, &d));
+                match (&a[1], &d[1]) {
+                    (super::ValueVariant::List(a1), super::ValueVariant::List(d1)) => assert!(std::rc::Rc::ptr_eq(a1, d1)),
+                    _ => panic!("list values expected"),
+                }
+            }
+            _ => panic!("list values expected"),
+        }
+    }
+
+    #[test]
+    fn mut_method_test() {
+        let mut interp = run_prog("fn squares(n) {\n xs = []\n for i in [0, n] {\n xs.push(i * i)\n }\n return xs\n }\n\
+                                   a = squares(4) \n\
+                                   b = a \n\
+                                   last = b.pop() \n\
+                                   s = \"ab\" \n\
+                                   s.push_str(\"c\") \n\
+                                   m = {x: 1, y: 2} \n\
+                                   y = m.remove(\"y\") \n\
+                                   z = m.remove(\"z\") \n\
+                                   e = [] \n\
+                                   p = e.pop()");
+        let mut var = |name: &str| interp._get_var_value(name).unwrap();
+        assert_eq!(var("a").to_string(), "[0, 1, 4, 9]");
+        assert_eq!(var("b").to_string(), "[0, 1, 4]");
+        assert_eq!(var("last"), super::ValueVariant::Integer(9));
+        assert_eq!(var("s"), super::ValueVariant::String("abc".into()));
+        assert_eq!(var("m").to_string(), "{\"x\": 1}");
+        assert_eq!(var("y"), super::ValueVariant::Integer(2));
+        assert_eq!(var("z"), super::ValueVariant::Nil);
+        assert_eq!(var("p"), super::ValueVariant::Nil);
+
+        let mut run = |prog: &str| {
+            let expr = crate::ex_core::parser::Parser::new(&crate::ex_core::tokenize(prog).unwrap()).parse().unwrap();
+            interp.interp_expr(expr)
+        };
+        assert_eq!(run("[1].push(2)").unwrap_err(), "method push changes the value, it can be called only on a variable, not on a temporary list value");
+        assert_eq!(run("a.push()").unwrap_err(), "Error with method push : method arg expected 1 argument, find 0");
+        assert_eq!(run("s.push_str(1)").unwrap_err(), "Error with method push_str : exptected string argument, find 1");
+    }
+
+    #[test]
+    fn char_and_bytes_test() {
+        let path = std::env::temp_dir().join(format!("ex_bytes_test_{}", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let mut interp = run_prog(&format!("s = \"añb\" \n\
+                                   cs = s.chars() \n\
+                                   c = s[1] \n\
+                                   code = c.code() \n\
+                                   d = 100.to_char() \n\
+                                   t = cs[0] + \"-\" + cs[2] + 120.to_char() \n\
+                                   less = cs[0] < cs[2] \n\
+                                   digit = 55.to_char().is_digit() \n\
+                                   eq = [c == \"ñ\", \"ñ\" == c, c != \"n\", \"ñb\" != c, c == \"\", cs[0] == \"a\"] \n\
+                                   commas = 0 \n\
+                                   line = \"a,b,\".chars() \n\
+                                   for i in [0, line.len()] {{\n if line[i] == \",\" {{\n commas += 1\n }}\n }} \n\
+                                   match c {{\n \"n\" => kind = 1\n \"ñ\" => kind = 2\n _ => kind = 3\n }} \n\
+                                   utf8 = s.encode(\"utf8\") \n\
+                                   latin1 = s.encode(\"latin1\") \n\
+                                   n = utf8.len() \n\
+                                   byte = utf8[1] \n\
+                                   back = latin1.decode(\"latin1\") \n\
+                                   b64 = [\"h\".encode(\"utf8\").encode(\"base64\"), \"hi\".encode(\"utf8\").encode(\"base64\"), utf8.encode(\"base64\")] \n\
+                                   hex = utf8.encode(\"hex\") \n\
+                                   raw = \"ff00\".decode(\"hex\") + \"YQ==\".decode(\"base64\") \n\
+                                   write_file(\"{0}\", raw) \n\
+                                   same = read_bytes(\"{0}\") == raw", path));
+        std::fs::remove_file(&path).unwrap();
+        let mut var = |name: &str| interp._get_var_value(name).unwrap();
+        assert_eq!(var("cs").to_string(), "['a', 'ñ', 'b']");
+        assert_eq!(var("c"), super::ValueVariant::Char('ñ'));
+        assert_eq!(var("code"), super::ValueVariant::Integer(0xF1));
+        assert_eq!(var("d").to_string(), "d");
+        assert_eq!(var("t"), super::ValueVariant::String("a-bx".into()));
+        assert_eq!(var("less"), super::ValueVariant::Bool(true));
+        assert_eq!(var("digit"), super::ValueVariant::Bool(true));
+        // chars are equal to strings of one same char
+        assert_eq!(var("eq").to_string(), "[true, true, true, true, false, true]");
+        assert_eq!(var("commas"), super::ValueVariant::Integer(2));
+        assert_eq!(var("kind"), super::ValueVariant::Integer(2));
+        assert_eq!(var("utf8").to_string(), "b\"a\\xc3\\xb1b\"");
+        assert_eq!(var("latin1").to_string(), "b\"a\\xf1b\"");
+        assert_eq!(var("n"), super::ValueVariant::Integer(4));
+        assert_eq!(var("byte"), super::ValueVariant::Integer(0xC3));
+        assert_eq!(var("back"), var("s"));
+        assert_eq!(var("b64").to_string(), "[\"aA==\", \"aGk=\", \"YcOxYg==\"]");
+        assert_eq!(var("hex"), super::ValueVariant::String("61c3b162".into()));
+        assert_eq!(var("raw").to_string(), "b\"\\xff\\x00a\"");
+        assert_eq!(var("same"), super::ValueVariant::Bool(true));
+
+        let mut run = |prog: &str| {
+            let expr = crate::ex_core::parser::Parser::new(&crate::ex_core::tokenize(prog).unwrap()).parse().unwrap();
+            interp.interp_expr(expr)
+        };
+        assert_eq!(run("x = latin1.decode(\"utf8\")").unwrap_err(), "Error with method decode : bytes are not valid utf8: invalid byte at 1");
+        assert_eq!(run("x = \"€\".encode(\"latin1\")").unwrap_err(), "Error with method encode : char '€' can't be encoded in latin1");
+        assert_eq!(run("x = \"a\".encode(\"ascii\")").unwrap_err(), "Error with method encode : unknown text encoding 'ascii', expected utf8 or latin1");
+        assert_eq!(run("x = \"abc\".decode(\"hex\")").unwrap_err(), "Error with method decode : not valid hex string \"abc\"");
+        assert_eq!(run("utf8[0] = 1").unwrap_err(), "items of bytes value can't be assigned");
+    }
+
+    #[test]
+    fn regex_test() {
+        let mut interp = run_prog("line = \"2024-01-02 ERROR disk full, code=28\" \n\
+                                   m = re_match(\"(\\d+)-(\\d+)-(\\d+) (\\w+)( debug)?\", line) \n\
+                                   named = re_match(\"(?P<level>[A-Z]+) .*code=(?P<code>\\d+)\", line) \n\
+                                   none = re_match(\"WARN\", line) \n\
+                                   nums = re_find_all(\"\\d+\", line) \n\
+                                   pairs = re_find_all(\"(\\w)=(\\d)\", \"a=1 b=2\") \n\
+                                   date = re_replace(\"(\\d+)-(\\d+)-(?P<day>\\d+)\", line, \"${day}.$2.$1\") \n\
+                                   words = re_split(\",? +\", \"disk full,  code\") \n\
+                                   for i in [0, 3] {\n n = re_find_all(\"\\d+\", line) \n }");
+        let mut var = |name: &str| interp._get_var_value(name).unwrap();
+        assert_eq!(var("m").to_string(), "[\"2024-01-02 ERROR\", \"2024\", \"01\", \"02\", \"ERROR\", nil]");
+        assert_eq!(var("named").to_string(), "{\"code\": \"28\", \"level\": \"ERROR\"}");
+        assert_eq!(var("none"), super::ValueVariant::Nil);
+        assert_eq!(var("nums").to_string(), "[\"2024\", \"01\", \"02\", \"28\"]");
+        assert_eq!(var("pairs").to_string(), "[[\"a=1\", \"a\", \"1\"], [\"b=2\", \"b\", \"2\"]]");
+        assert_eq!(var("date").to_string(), "02.01.2024 ERROR disk full, code=28");
+        assert_eq!(var("words").to_string(), "[\"disk\", \"full\", \"code\"]");
+
+        let expr = crate::ex_core::parser::Parser::new(&crate::ex_core::tokenize("x = re_match(\"(\", \"s\")").unwrap()).parse().unwrap();
+        let err = interp.interp_expr(expr).unwrap_err();
+        assert!(err.starts_with("Error with function re_match : invalid pattern '(': "), "{}", err);
+    }
+
+    /// run with 'cargo test --release -- --ignored vm_benchmark --nocapture'.
+    /// Recorded numbers of the while loop on one machine: 0.83s with the tree-walking interpreter,
+    /// that was replaced by the bytecode VM, and 0.35s with the VM, about 2.4x faster
+    #[test]
+    #[ignore]
+    fn vm_benchmark() {
+        let start = std::time::Instant::now();
+        let mut interp = run_prog("i = 1000000\n s = 0\n while i > 0 {\n s = s + i % 7\n i = i - 1\n }");
+        println!("while loop: {:?}", start.elapsed());
+        let start = std::time::Instant::now();
+        let mut fib_interp = run_prog("fn fib(n) {\n if n < 2 {\n return n\n }\n return fib(n - 1) + fib(n - 2)\n }\n f = fib(22)");
+        println!("fib(22): {:?}", start.elapsed());
+        assert_eq!(interp._get_var_value("s").unwrap(), super::ValueVariant::Integer(2999998));
+        assert_eq!(fib_interp._get_var_value("f").unwrap(), super::ValueVariant::Integer(17711));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn spawn_test() {
+        let mut interp = run_prog("p = spawn(\"sh\", [\"-c\", \"cat; echo; echo done; exit 3\"]) \n\
+                                   p.write_stdin(\"hello\") \n\
+                                   p.close_stdin() \n\
+                                   lines = p.stdout_lines() \n\
+                                   code = p.wait() \n\
+                                   s = spawn(\"sleep\", [\"10\"]) \n\
+                                   s.kill(\"TERM\") \n\
+                                   s_code = s.wait()");
+        assert_eq!(interp._get_var_value("lines").unwrap().to_string(), "[\"hello\", \"done\"]");
+        assert_eq!(interp._get_var_value("code").unwrap(), super::ValueVariant::Integer(3));
+        assert_eq!(interp._get_var_value("s_code").unwrap(), super::ValueVariant::Integer(128 + 15));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn spawn_drop_test() {
+        let dir = std::env::temp_dir().join(format!("ex_spawn_drop_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let marker = dir.join("marker");
+        let mut interp = run_prog(&format!("spawn(\"sh\", [\"-c\", \"sleep 0.2; touch {}\"]) \n\
+                                            done = spawn(\"true\") \n\
+                                            k = spawn(\"sleep\", [\"10\"], {{\"kill_on_drop\": true}}) \n\
+                                            q = k \n\
+                                            pids = [done.pid(), k.pid()] \n\
+                                            k = nil", marker.to_str().unwrap()));
+        let pids = interp._get_var_value("pids").unwrap().to_string();
+        let pids: Vec<i64> = pids.trim_matches(['[', ']']).split(", ").map(|pid| pid.parse().unwrap()).collect();
+        // SAFETY: kill with signal 0 only checks, that the process exists
+        let exists = |pid: i64| unsafe { libc::kill(pid as libc::pid_t, 0) } == 0;
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        // the handle of the killed child is still shared by q
+        assert!(exists(pids[1]));
+        drop(interp);
+        // the exited child is reaped and the child with kill_on_drop is killed
+        assert!(!exists(pids[0]));
+        assert!(!exists(pids[1]));
+        // the child without kill_on_drop keeps running, after its handle is dropped
+        let start = std::time::Instant::now();
+        while !marker.exists() && start.elapsed() < std::time::Duration::from_secs(5) {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        assert!(marker.exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn spawn_bytes_test() {
+        let mut interp = run_prog("p = spawn(\"cat\") \n\
+                                   p.write_stdin(\"61ff0a62\".decode(\"hex\")) \n\
+                                   p.close_stdin() \n\
+                                   out = p.stdout_bytes()");
+        assert_eq!(interp._get_var_value("out").unwrap().to_string(), "b\"a\\xff\\nb\"");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn spawn_timeout_test() {
+        let prog = "p = spawn(\"sleep\", [\"10\"], {\"timeout\": 0.1}) \n\
+                    p.wait()".to_string();
+        let expr = crate::ex_core::parser::Parser::new(&crate::ex_core::tokenize(&prog).unwrap()).parse().unwrap();
+        let mut interp = crate::ex_core::interp::Interpreter::new();
+        let err = interp.interp_expr(expr).unwrap_err();
+        assert!(err.contains("timed out"), "{}", err);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn spawn_args_expansion_test() {
+        let mut interp = run_prog("p = spawn(\"echo\", [\"x{a,b}\", [quote(\"{c,d}\"), 1], \"\\*\"]) \n\
+                                   lines = p.stdout_lines()");
+        assert_eq!(interp._get_var_value("lines").unwrap().to_string(), "[\"xa xb {c,d} 1 *\"]");
+    }
 }
@@ -2,9 +2,14 @@ mod tokenizer;
 mod expressions;
 mod parser;
 pub(crate) mod interp;
+mod bytecode;
+mod compiler;
+mod resolver;
+mod module;
 
 pub use tokenizer::*;
 pub use expressions::*;
 pub use parser::*;
 pub use interp::*;
+pub use module::*;
 
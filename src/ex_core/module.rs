@@ -0,0 +1,95 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use super::{tokenize, Interpreter, Parser};
+
+/// interpreter of the imported module, that holds its functions and variables
+pub type ModuleRef = Rc<RefCell<Interpreter>>;
+
+/// Loads modules and caches them, so every file is interpreted only once.
+/// The loader is shared by the interpreter of the script and interpreters of all its modules
+#[derive(Default)]
+pub struct ModuleLoader {
+    modules: HashMap<PathBuf, ModuleRef>,
+    /// files, that are being loaded, in the order of imports
+    loading: Vec<PathBuf>,
+}
+
+impl ModuleLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// marks the script file as being loaded, so importing it from its modules is a circular import
+    pub fn set_root(&mut self, path: &Path) {
+        if let Ok(path) = path.canonicalize() {
+            self.loading = vec![path];
+        }
+    }
+
+    /// directories, where modules are searched: the directory of the importing file
+    /// or the current directory, then directories from the EX_PATH environment variable
+    fn search_dirs(importer_dir: Option<&Path>) -> Vec<PathBuf> {
+        let mut dirs = vec![importer_dir.map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from("."))];
+        if let Some(ex_path) = env::var_os("EX_PATH") {
+            dirs.extend(env::split_paths(&ex_path).filter(|dir| !dir.as_os_str().is_empty()));
+        }
+        dirs
+    }
+
+    /// finds the module file and returns its canonical path
+    fn resolve(path: &str, importer_dir: Option<&Path>) -> Result<PathBuf, String> {
+        let candidates = if Path::new(path).is_absolute() {
+            vec![PathBuf::from(path)]
+        } else {
+            Self::search_dirs(importer_dir).into_iter().map(|dir| dir.join(path)).collect()
+        };
+        candidates.iter()
+            .find(|candidate| candidate.is_file())
+            .and_then(|candidate| candidate.canonicalize().ok())
+            .ok_or(format!("can't find module '{}'", path))
+    }
+}
+
+/// returns the cached module or interprets the module file,
+/// 'importer_dir' is the directory of the file with the import statement
+pub fn load_module(loader: &Rc<RefCell<ModuleLoader>>, path: &str, importer_dir: Option<&Path>) -> Result<ModuleRef, String> {
+    let file = ModuleLoader::resolve(path, importer_dir)?;
+    {
+        let mut state = loader.borrow_mut();
+        if let Some(module) = state.modules.get(&file) {
+            return Ok(module.clone());
+        }
+        if state.loading.contains(&file) {
+            let chain: Vec<String> = state.loading.iter()
+                .skip_while(|loading| **loading != file)
+                .chain(std::iter::once(&file))
+                .map(|loading| loading.display().to_string())
+                .collect();
+            return Err(format!("circular import: {}", chain.join(" -> ")));
+        }
+        state.loading.push(file.clone());
+    }
+    let result = interp_module(loader, &file);
+    let mut state = loader.borrow_mut();
+    state.loading.pop();
+    let module: ModuleRef = Rc::new(RefCell::new(result?));
+    state.modules.insert(file, module.clone());
+    Ok(module)
+}
+
+fn interp_module(loader: &Rc<RefCell<ModuleLoader>>, file: &Path) -> Result<Interpreter, String> {
+    let code = fs::read_to_string(file).map_err(|err| format!("can't read module {}: {}", file.display(), err))?;
+    let tokens = tokenize(&code).map_err(|err| format!("lexer error in module {}: {}", file.display(), err))?;
+    let expr = Parser::new(&tokens).parse().map_err(|err_msg| format!("parsing error in module {}: {}", file.display(), err_msg))?;
+    let mut interp = Interpreter::with_module_loader(loader.clone(), file.parent().map(Path::to_path_buf));
+    for warning in interp.check(expr.as_ref()) {
+        eprintln!("Warning in module {}: {}", file.display(), warning);
+    }
+    interp.interp_expr(expr).map_err(|err_msg| format!("error in module {}: {}", file.display(), err_msg))?;
+    Ok(interp)
+}
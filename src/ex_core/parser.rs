@@ -1,34 +1,148 @@
+use std::fmt;
 use std::mem;
+use std::path::Path;
 
-use super::{Expression, Token, StatementListExpression, AssignmentExpression, FunctionCallExpression, FunctionDefExpression,   BinaryExpression, IntLiteralExpression, FloatLiteralExpression, StringLiteralExpression, UnaryExpression, NameExpression, BoolLiteralExpression, IfExpression, WhileExpression, ForExpression, MethodCallExpression, AnonymousMethodExpression};
+use super::{Expression, Token, StatementListExpression, AssignmentExpression, FunctionCallExpression, FunctionDefExpression,   BinaryExpression, IntLiteralExpression, FloatLiteralExpression, StringLiteralExpression, UnaryExpression, NameExpression, BoolLiteralExpression, IfExpression, WhileExpression, ForExpression, MethodCallExpression, AnonymousMethodExpression, ListExpression, MapExpression, AssignmentTarget, CompoundAssignmentExpression, MultipleAssignmentExpression, IndexExpression, FieldExpression, MatchExpression, MatchArm, MatchPattern, NilLiteralExpression, ReturnExpression, ImportExpression};
 
 pub type ParseResult = Result<Box<dyn Expression>, String>;
 
+/// arguments of the function definition: names, default values of the last arguments and the variadic argument
+type FuncDefArgs = (Vec<String>, Vec<Box<dyn Expression>>, Option<String>);
+
+/// positional and keyword arguments of the function call
+type CallArgs = (Vec<Box<dyn Expression>>, Vec<(String, Box<dyn Expression>)>);
+
+/// Syntax error with the line of the token, where it was found
+#[derive(Clone, PartialEq, Debug)]
+pub struct ParseError {
+    pub message: String,
+    pub line: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Associativity {
+    Left,
+    Right,
+}
+
+/// binary operators with their precedence and associativity,
+/// operators with a greater precedence bind tighter
+const BINARY_OPERATORS: [(Token, u8, Associativity); 13] = [
+    (Token::Coalesce,   1, Associativity::Right),
+    (Token::Eq,         2, Associativity::Left),
+    (Token::NotEq,      2, Associativity::Left),
+    (Token::More,       3, Associativity::Left),
+    (Token::MoreEq,     3, Associativity::Left),
+    (Token::Less,       3, Associativity::Left),
+    (Token::LessEq,     3, Associativity::Left),
+    (Token::Plus,       4, Associativity::Left),
+    (Token::Minus,      4, Associativity::Left),
+    (Token::Multi,      5, Associativity::Left),
+    (Token::Devide,     5, Associativity::Left),
+    (Token::Percent,    5, Associativity::Left),
+    (Token::Pow,        7, Associativity::Right),
+];
+
+/// precedence of unary plus and minus, so -2 ** 2 is -(2 ** 2) and -2 * 3 is (-2) * 3
+const PREFIX_PRECEDENCE: u8 = 6;
+
+fn binary_operator(token: &Token) -> Option<(Token, u8, Associativity)> {
+    BINARY_OPERATORS.iter().find(|(op, _, _)| op == token).cloned()
+}
+
+/// binary operator of the compound assignment, like '+' for '+='
+fn compound_assignment_operator(token: &Token) -> Option<Token> {
+    match token {
+        Token::PlusAssignment =>    Some(Token::Plus),
+        Token::MinusAssignment =>   Some(Token::Minus),
+        Token::MultiAssignment =>   Some(Token::Multi),
+        Token::DevideAssignment =>  Some(Token::Devide),
+        Token::PercentAssignment => Some(Token::Percent),
+        _ =>                        None
+    }
+}
+
+/// statement is an assignment if there is an assignment operator
+/// outside of brackets before the end of the line
+pub fn is_assignment_statement(tokens: &[Token]) -> bool {
+    let mut depth = 0;
+    for token in tokens.iter() {
+        match token {
+            Token::OpenBracket | Token::OpenSquareBracket | Token::OpenCurlyBracket => depth += 1,
+            Token::CloseBracket | Token::CloseSquareBracket | Token::CloseCurlyBraket => {
+                if depth == 0 {
+                    return false;
+                }
+                depth -= 1;
+            }
+            Token::NewLine if depth == 0 => return false,
+            Token::Assignment if depth == 0 => return true,
+            _ if depth == 0 && compound_assignment_operator(token).is_some() => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
 pub struct Parser {
     tokens: Vec<Token>,
-    pos: usize
+    pos: usize,
+    /// lines of tokens, the last item is the line of the end of input
+    lines: Vec<usize>,
+    errors: Vec<ParseError>,
 }
 
 impl Parser {
     pub fn new(tokens: &Vec<Token>) -> Self {
+        let mut line = 1;
+        let mut lines = Vec::with_capacity(tokens.len() + 1);
+        for token in tokens.iter() {
+            lines.push(line);
+            if *token == Token::NewLine {
+                line += 1;
+            }
+        }
+        lines.push(line);
         Parser {
             tokens: tokens.clone(),
-            pos: 0
+            pos: 0,
+            lines,
+            errors: Vec::new(),
         }
     }
 
+    /// parses the whole program, all syntax errors are joined in the error message
     pub fn parse(&mut self) -> ParseResult {
-        self.program()
-    } 
+        let (program, errors) = self.parse_partial();
+        if errors.is_empty() {
+            return Ok(program);
+        }
+        let messages: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+        Err(messages.join("\n"))
+    }
 
+    /// parses the whole program, skipping statements with syntax errors,
+    /// returns the program without these statements and the list of errors
+    pub fn parse_partial(&mut self) -> (Box<dyn Expression>, Vec<ParseError>) {
+        self.pos = 0;
+        self.errors.clear();
+        let program = self.program();
+        (program, mem::take(&mut self.errors))
+    }
 
-    fn program(&mut self) -> ParseResult {
+    fn program(&mut self) -> Box<dyn Expression> {
         self.statement_list()
     }
 
     /// 'statement_list' function match next syntax pattern:
     /// {statement}*
-    fn statement_list(&mut self) -> ParseResult {
+    fn statement_list(&mut self) -> Box<dyn Expression> {
         let mut statements  = Vec::new();
         loop {
             if let Some(current_token) = self.peek_current_token() {
@@ -38,30 +152,63 @@ impl Parser {
                         continue;
                     }
                     _ => {
-                        let statement = self.statement()?;
-                        statements.push(statement);
+                        if let Some(statement) = self.recovering_statement() {
+                            statements.push(statement);
+                        }
                     }
                 }
             } else {
                 break;
             }
         }
-        Ok(Box::new(StatementListExpression::new(statements)))
+        Box::new(StatementListExpression::new(statements))
+    }
+
+    /// parses the statement, on error stores it and skips tokens till the end
+    /// of the statement line or till the closing brace of the enclosing code block
+    fn recovering_statement(&mut self) -> Option<Box<dyn Expression>> {
+        let start_pos = self.pos;
+        match self.statement() {
+            Ok(statement) => Some(statement),
+            Err(message) => {
+                let line = self.current_line();
+                self.errors.push(ParseError { message, line });
+                // code blocks and map literals of the statement are skipped as a whole
+                self.pos = start_pos;
+                let mut depth = 0;
+                while let Some(token) = self.peek_current_token() {
+                    match token {
+                        Token::NewLine if depth == 0 => {
+                            self.advance();
+                            break;
+                        }
+                        Token::CloseCurlyBraket if depth == 0 => break,
+                        Token::OpenCurlyBracket => depth += 1,
+                        Token::CloseCurlyBraket => depth -= 1,
+                        _ => {}
+                    }
+                    self.advance();
+                }
+                // stray closing brace can't start a statement, so it's skipped
+                if self.pos == start_pos {
+                    self.advance();
+                }
+                None
+            }
+        }
+    }
+
+    /// line of the current token, that is the number of line breaks before it
+    fn current_line(&self) -> usize {
+        self.lines[self.pos.min(self.tokens.len())]
     }
 
     /// 'statement' function match next syntax pattern:
-    /// {assignment_statement} | {function call} | {function def} | {return_expr} | {expr}
+    /// {assignment_statement} | {function def} | {while} | {for} | {if} | {match} | {import} | {return_expr} | {expr}
     fn statement(&mut self) -> ParseResult {
         if let Some(_) = self.peek_current_token() {
-            if self.current_token_is(Token::Name("".to_string())) && 
-               self.nth_token_is(1, Token::Assignment) {
+            if self.is_assignment_statement() {
                 return self.assignment_statement();
-            }  else if self.current_token_is(Token::Name("".to_string())) && 
-                      self.nth_token_is(1, Token::OpenBracket) {
-                return self.function_call_statement();        
-            } else if self.current_token_is(Token::Name("".to_string())) && 
-                      self.nth_token_is(1, Token::Dot) {
-                return self.method_call_statement();        
             } else if self.current_token_is(Token::Fn) {
                 return self.function_def_statement();
             } else if self.current_token_is(Token::While) {
@@ -70,12 +217,15 @@ impl Parser {
                 return self.for_statement();
             } else if self.current_token_is(Token::If) {
                 return self.if_statement();
+            } else if self.current_token_is(Token::Match) {
+                return self.match_statement();
+            } else if self.current_token_is(Token::Import) {
+                return self.import_statement();
             } else if self.current_token_is(Token::NewLine) {
                 self.advance();
                 return self.statement();
             } else if self.current_token_is(Token::Return) {
-                self.advance();
-                return self.expression();    
+                return self.return_statement();
             } else {
                 return self.expression();    
             }
@@ -88,8 +238,10 @@ impl Parser {
         let mut result : Vec<Box<dyn Expression>> = Vec::new();
         self.eat(Token::OpenCurlyBracket)?;
         self.skip_new_lines();
-        while !self.current_token_is(Token::CloseCurlyBraket) {
-            result.push(self.statement()?); 
+        while self.peek_current_token().is_some() && !self.current_token_is(Token::CloseCurlyBraket) {
+            if let Some(statement) = self.recovering_statement() {
+                result.push(statement);
+            }
             self.skip_new_lines();
         }
         self.skip_new_lines();
@@ -124,154 +276,252 @@ impl Parser {
         let mut false_expressions: Vec<Box<dyn Expression>> = Vec::new();
         if self.current_token_is(Token::Else) {
             self.eat(Token::Else)?;
-            false_expressions = self.parse_statements_in_curly_braces()?;
+            if self.current_token_is(Token::If) {
+                false_expressions.push(self.if_statement()?);
+            } else {
+                false_expressions = self.parse_statements_in_curly_braces()?;
+            }
         }
         return Ok(Box::new(IfExpression::new(if_expr, true_expressions, false_expressions)));
     }
 
-    /// 'assignment_statement' function match next syntax pattern:
-    /// let NAME = {expr}
-    fn assignment_statement(&mut self) -> ParseResult {
-        if let Some(name_token) = self.peek_current_token() {
-            match name_token {
-                Token::Name(name) => {
-                    self.advance();
-                    let eat_result = self.eat(Token::Assignment);
-                    if let Ok(()) = eat_result {
-                        let value = self.statement()?;
-                        return Ok(Box::new(AssignmentExpression::new(name, value)));
-                    } else if let Err(error_message) = eat_result {
-                        return Err(error_message);                        
-                    }  
-                }
-                _ => {
-                    return Err(String::from("expected 'name' token in assignment statement"));
-                }
+    /// 'match_statement' function match next syntax pattern:
+    /// match {expr} { [{pattern} [| {pattern}]* [if {expr}] => [{statement} | { {statement}* }] NEWLINE]* }
+    fn match_statement(&mut self) -> ParseResult {
+        self.eat(Token::Match)?;
+        let value = self.expression()?;
+        self.eat(Token::OpenCurlyBracket)?;
+        let mut arms = Vec::new();
+        self.skip_new_lines();
+        while self.peek_current_token().is_some() && !self.current_token_is(Token::CloseCurlyBraket) {
+            let mut patterns = vec![self.match_pattern()?];
+            while self.current_token_is(Token::Pipe) {
+                self.advance();
+                patterns.push(self.match_pattern()?);
+            }
+            let mut guard = None;
+            if self.current_token_is(Token::If) {
+                self.advance();
+                guard = Some(self.expression()?);
             }
+            self.eat(Token::Arrow)?;
+            let body = if self.current_token_is(Token::OpenCurlyBracket) {
+                self.parse_statements_in_curly_braces()?
+            } else {
+                vec![self.statement()?]
+            };
+            arms.push(MatchArm { patterns, guard, body });
+            self.skip_new_lines();
         }
-        return Err(String::from("no token in assignment statement"));
+        self.eat(Token::CloseCurlyBraket)?;
+        Ok(Box::new(MatchExpression::new(value, arms)))
     }
 
-
-    /// 'function_call_statement' function match next syntax pattern:
-    /// f_name ([expt,]*)
-    fn function_call_statement(&mut self) -> ParseResult {
-        let f_name = self.parse_name()?;
-        let f_args = self.parse_func_call_args()?;
-        return Ok(Box::new(FunctionCallExpression::new(f_name, f_args)));
+    /// 'match_pattern' function match next syntax pattern:
+    /// _ | NAME | {literal} | {literal}..{literal} | {literal}..={literal}
+    fn match_pattern(&mut self) -> Result<MatchPattern, String> {
+        match self.peek_current_token() {
+            Some(Token::Name(name)) => {
+                self.advance();
+                if name == "_" {
+                    Ok(MatchPattern::Wildcard)
+                } else {
+                    Ok(MatchPattern::Binding(name))
+                }
+            }
+            Some(Token::IntLiteral(_) | Token::FloatLiteral(_) | Token::StringLiteral(_) | Token::True | Token::False | Token::Nil | Token::Minus | Token::Plus) => {
+                let low = self.prefix_expression()?;
+                if !(self.current_token_is(Token::Dot) && self.nth_token_is(1, Token::Dot)) {
+                    return Ok(MatchPattern::Value(low));
+                }
+                self.advance();
+                self.advance();
+                let inclusive = self.current_token_is(Token::Assignment);
+                if inclusive {
+                    self.advance();
+                }
+                let high = self.prefix_expression()?;
+                Ok(MatchPattern::Range { low, high, inclusive })
+            }
+            Some(token) => Err(format!("not valid match pattern {}", token)),
+            None => Err(String::from("expected match pattern, found no token")),
+        }
     }
 
-    fn function_def_statement(&mut self) -> ParseResult {
-        self.eat(Token::Fn)?;
-        let f_name = self.parse_name()?;
-        let f_args = self.parse_func_def_args()?;
-        let f_body = self.parse_statements_in_curly_braces()?;
-        return Ok(Box::new(FunctionDefExpression::new(f_name, f_args, f_body)));
+    /// 'import_statement' function match next syntax pattern:
+    /// import [STRING|NAME] [as NAME]
+    fn import_statement(&mut self) -> ParseResult {
+        self.eat(Token::Import)?;
+        let path = match self.peek_current_token() {
+            Some(Token::StringLiteral(path)) => path,
+            Some(Token::Name(name)) => format!("{}.ex", name),
+            Some(token) => return Err(format!("expected module name or path, find token {}", token)),
+            None => return Err(String::from("expected module name or path, found no token")),
+        };
+        self.advance();
+        let namespace = if self.current_token_is(Token::As) {
+            self.advance();
+            self.parse_name()?
+        } else {
+            // namespace of 'import "lib/utils.ex"' is 'utils'
+            let stem = Path::new(&path).file_stem().and_then(|stem| stem.to_str()).unwrap_or_default().to_string();
+            let is_name = stem.chars().next().is_some_and(|ch| !ch.is_ascii_digit())
+                && stem.chars().all(|ch| ch.is_alphanumeric() || ch == '_');
+            if !is_name {
+                return Err(format!("module file name '{}' is not a valid name, use 'import \"{}\" as NAME'", stem, path));
+            }
+            stem
+        };
+        Ok(Box::new(ImportExpression::new(path, namespace)))
     }
 
-    fn method_call_statement(&mut self) -> ParseResult {
-        let self_name = self.parse_name()?;
-        self.eat(Token::Dot)?;
-        let method_name = self.parse_name()?;
-        let args = self.parse_func_call_args()?;
-        return Ok(Box::new(MethodCallExpression::new(self_name, method_name, args)));
+    fn is_assignment_statement(&self) -> bool {
+        is_assignment_statement(&self.tokens[self.pos..])
     }
 
-    fn expression(&mut self) -> ParseResult {
-        return self.equality();
-    }
-    
-    fn equality(&mut self) -> ParseResult {
-        let mut result = self.compression()?;
-        loop {
-            if let Some(token) = self.peek_current_token() {
-                match token {
-                    Token::Eq | Token::NotEq => {
-                        self.advance();
-                        let expr = self.compression()?;
-                        result = Box::new(BinaryExpression::new(token,result,expr));
-                    }
-                    _ => {
-                        break;
-                    }
-                }
+    /// 'assignment_statement' function match next syntax pattern:
+    /// {target} [, {target}]* = {expr} [, {expr}]* | {target} [PLUS_ASSIGNMENT|MINUS_ASSIGNMENT|...] {expr}
+    fn assignment_statement(&mut self) -> ParseResult {
+        let mut targets = vec![self.assignment_target()?];
+        while self.current_token_is(Token::Comma) {
+            self.advance();
+            targets.push(self.assignment_target()?);
+        }
+        let Some(op_token) = self.peek_current_token() else {
+            return Err(String::from("expected assignment operator, found no token"));
+        };
+        self.advance();
+        if self.current_token_is(Token::NewLine) || self.peek_current_token().is_none() {
+            return match &targets[..] {
+                [AssignmentTarget::Name(name)] => Err(format!("expected value of '{}' in assignment statement", name)),
+                _ => Err(String::from("expected value in assignment statement")),
+            };
+        }
+        if let Some(op) = compound_assignment_operator(&op_token) {
+            if targets.len() != 1 || matches!(targets[0], AssignmentTarget::List(_) | AssignmentTarget::Map(_)) {
+                return Err(format!("{} expects a single name, index or field target", op_token));
             }
+            let value = self.expression()?;
+            return Ok(Box::new(CompoundAssignmentExpression::new(targets.remove(0), op, value)));
         }
-        return Ok(result);
+        if op_token != Token::Assignment {
+            return Err(format!("expected assignment operator, find token {}", op_token));
+        }
+        let mut values = vec![self.expression()?];
+        while self.current_token_is(Token::Comma) {
+            self.advance();
+            values.push(self.expression()?);
+        }
+        if let ([AssignmentTarget::Name(name)], 1) = (&targets[..], values.len()) {
+            return Ok(Box::new(AssignmentExpression::new(name.clone(), values.remove(0))));
+        }
+        return Ok(Box::new(MultipleAssignmentExpression::new(targets, values)));
     }
 
-    fn compression(&mut self) -> ParseResult {
-        let mut result = self.term()?;
-        loop {
-            if let Some(token) = self.peek_current_token() {
-                match token {
-                    Token::More | Token::MoreEq | Token::Less | Token::LessEq => {
+    /// 'assignment_target' function match next syntax pattern:
+    /// NAME [[{expr}] | .NAME]* | [{target} [, {target}]*] | {NAME [, NAME]*}
+    fn assignment_target(&mut self) -> Result<AssignmentTarget, String> {
+        match self.peek_current_token() {
+            Some(Token::Name(name)) => {
+                self.advance();
+                let mut target = AssignmentTarget::Name(name);
+                loop {
+                    if self.current_token_is(Token::OpenSquareBracket) {
                         self.advance();
-                        let expr = self.term()?;
-                        result = Box::new(BinaryExpression::new(token,result,expr));
-                    }
-                    _ => {
-                        break;
+                        let index = self.expression()?;
+                        self.eat(Token::CloseSquareBracket)?;
+                        target = AssignmentTarget::Index(Box::new(target), index);
+                    } else if self.current_token_is(Token::Dot) {
+                        self.advance();
+                        let field = self.parse_name()?;
+                        target = AssignmentTarget::Field(Box::new(target), field);
+                    } else {
+                        return Ok(target);
                     }
                 }
             }
-        }
-        return Ok(result);
-    }
-           
-
-    /// 'expr' function match next syntax pattern:
-    /// {term} [[PLUS|MINUS] {term}]*
-    fn term(&mut self) -> ParseResult {
-        let mut result = self.factor()?;
-        loop {
-            if let Some(token) = self.peek_current_token() {
-                match token {
-                    Token::Plus | Token::Minus => {
-                        self.advance();
-                        let expr = self.factor()?;
-                        result =  Box::new(BinaryExpression::new(token, result, expr));
+            Some(Token::OpenSquareBracket) => {
+                self.advance();
+                let mut targets = Vec::new();
+                while !self.current_token_is(Token::CloseSquareBracket) {
+                    targets.push(self.assignment_target()?);
+                    if !self.current_token_is(Token::CloseSquareBracket) {
+                        self.eat(Token::Comma)?;
                     }
-                    _ => {
-                        break;
+                }
+                self.eat(Token::CloseSquareBracket)?;
+                Ok(AssignmentTarget::List(targets))
+            }
+            Some(Token::OpenCurlyBracket) => {
+                self.advance();
+                let mut names = Vec::new();
+                while !self.current_token_is(Token::CloseCurlyBraket) {
+                    names.push(self.parse_name()?);
+                    if !self.current_token_is(Token::CloseCurlyBraket) {
+                        self.eat(Token::Comma)?;
                     }
                 }
-            } else {
-                break;
+                self.eat(Token::CloseCurlyBraket)?;
+                Ok(AssignmentTarget::Map(names))
             }
+            Some(token) => Err(format!("not valid assignment target {}", token)),
+            None => Err(String::from("expected assignment target, found no token")),
         }
-        return Ok(result);
     }
 
-    /// 'term' function match next syntax pattern:
-    /// {factor} [[MUL|DIV] {factor}]*
-    fn factor(&mut self) -> ParseResult {
-        let mut result = self.unary()?;
-        loop {
-            if let Some(token) = self.peek_current_token() {
-                match token {
-                    Token::Multi | Token::Devide => {
-                        self.advance();
-                        let expr = self.unary()?;
-                        result =  Box::new(BinaryExpression::new(token, result, expr));
-                    }
-                    _ => {
-                        break;
-                    }
-                }
-            } else {
+    /// 'return_statement' function match next syntax pattern:
+    /// return [{expr}]
+    fn return_statement(&mut self) -> ParseResult {
+        self.eat(Token::Return)?;
+        let value: Box<dyn Expression> = match self.peek_current_token() {
+            None | Some(Token::NewLine) | Some(Token::CloseCurlyBraket) => Box::new(NilLiteralExpression),
+            _ => self.expression()?,
+        };
+        Ok(Box::new(ReturnExpression::new(value)))
+    }
+
+    fn function_def_statement(&mut self) -> ParseResult {
+        self.eat(Token::Fn)?;
+        let f_name = self.parse_name()?;
+        let (f_args, f_defaults, f_rest) = self.parse_func_def_args()?;
+        let f_body = self.parse_statements_in_curly_braces()?;
+        return Ok(Box::new(FunctionDefExpression::new(f_name, f_args, f_defaults, f_rest, f_body)));
+    }
+
+    fn expression(&mut self) -> ParseResult {
+        return self.binary_expression(0);
+    }
+
+    /// 'binary_expression' function parses operands joined with binary operators,
+    /// which precedence is not less than 'min_precedence', see BINARY_OPERATORS
+    fn binary_expression(&mut self, min_precedence: u8) -> ParseResult {
+        let mut result = self.prefix_expression()?;
+        while let Some((op, precedence, associativity)) = self.peek_current_token().and_then(|token| binary_operator(&token)) {
+            if precedence < min_precedence {
                 break;
             }
+            self.advance();
+            // expression can be continued on the next line after the operator
+            self.skip_new_lines();
+            let right_min_precedence = match associativity {
+                Associativity::Left => precedence + 1,
+                Associativity::Right => precedence,
+            };
+            let right = self.binary_expression(right_min_precedence)?;
+            result = Box::new(BinaryExpression::new(op, result, right));
         }
         return Ok(result);
     }
 
-    fn unary(&mut self) -> ParseResult {
+    /// 'prefix_expression' function match next syntax pattern:
+    /// [PLUS|MINUS]* {anonymous_methods}
+    fn prefix_expression(&mut self) -> ParseResult {
         if let Some(token) = self.peek_current_token() {
             match token {
                 Token::Plus|Token::Minus => {
                     self.advance();
-                    let expr = self.unary()?;
+                    self.skip_new_lines();
+                    let expr = self.binary_expression(PREFIX_PRECEDENCE)?;
                     return Ok(Box::new(UnaryExpression::new(token, expr)));
                 }
                 _ => {}
@@ -280,14 +530,26 @@ impl Parser {
         return self.anonymous_methods();
     }
 
+    /// 'anonymous_methods' function match next syntax pattern:
+    /// {primary} [.NAME([expr,]*) | .NAME | [{expr}]]*
     fn anonymous_methods(&mut self) -> ParseResult {
         let mut result =  self.primary()?;
         loop {
             if self.current_token_is(Token::Dot) && self.nth_token_is(1, Token::Name("".to_string())) {
+                let line = self.current_line();
                 self.eat(Token::Dot)?;
-                let method_name = self.parse_name()?;
-                let args = self.parse_func_call_args()?;
-                result = Box::new(AnonymousMethodExpression::new(result, method_name, args));
+                let name = self.parse_name()?;
+                if self.current_token_is(Token::OpenBracket) {
+                    let args = self.parse_func_call_args()?;
+                    result = Box::new(AnonymousMethodExpression::new(result, name, args, line));
+                } else {
+                    result = Box::new(FieldExpression::new(result, name));
+                }
+            } else if self.current_token_is(Token::OpenSquareBracket) {
+                self.advance();
+                let index = self.expression()?;
+                self.eat(Token::CloseSquareBracket)?;
+                result = Box::new(IndexExpression::new(result, index));
             } else {
                 break;
             }
@@ -296,7 +558,9 @@ impl Parser {
     }
     
     fn primary(&mut self) -> ParseResult {
-        let current_token = self.peek_current_token().unwrap();
+        let Some(current_token) = self.peek_current_token() else {
+            return Err(String::from("expected expression, found end of input"));
+        };
         match current_token {
             Token::IntLiteral(i) => {
                 self.advance();
@@ -317,27 +581,103 @@ impl Parser {
                 return Ok(result); 
             }
             Token::Name(n) => {
+                if self.nth_token_is(1, Token::OpenBracket) {
+                    return self.function_call();
+                }
+                if self.nth_token_is(1, Token::Dot) && self.nth_token_is(2, Token::Name("".to_string())) && self.nth_token_is(3, Token::OpenBracket) {
+                    return self.method_call();
+                }
                 self.advance();
                 return Ok(Box::new(NameExpression::new(n)));
             }
+            Token::OpenSquareBracket => {
+                return self.list_literal();
+            }
+            Token::OpenCurlyBracket => {
+                return self.map_literal();
+            }
             Token::True => {
                 self.advance();
                 return Ok(Box::new(BoolLiteralExpression::new(true)));
             }
-            Token::False => {
+            Token::Nil => {
                 self.advance();
-                return Ok(Box::new(BoolLiteralExpression::new(false)));
+                return Ok(Box::new(NilLiteralExpression));
             }
-            Token::NewLine => {
+            Token::False => {
                 self.advance();
-                return self.primary();
+                return Ok(Box::new(BoolLiteralExpression::new(false)));
             }
             _ => {
-                return Err(format!("Not valid factor {}", current_token.to_string()));
+                return Err(format!("expected expression, found {}", current_token.to_string()));
             }
         }
     }
 
+    /// 'function_call' function match next syntax pattern:
+    /// f_name ([expt,]* [NAME: expr,]*)
+    fn function_call(&mut self) -> ParseResult {
+        let line = self.current_line();
+        let f_name = self.parse_name()?;
+        let (f_args, f_kwargs) = self.parse_call_args()?;
+        return Ok(Box::new(FunctionCallExpression::new(f_name, f_args, f_kwargs, line)));
+    }
+
+    /// 'method_call' function match next syntax pattern:
+    /// self_name.method_name ([expt,]*)
+    fn method_call(&mut self) -> ParseResult {
+        let line = self.current_line();
+        let self_name = self.parse_name()?;
+        self.eat(Token::Dot)?;
+        let method_name = self.parse_name()?;
+        let args = self.parse_func_call_args()?;
+        return Ok(Box::new(MethodCallExpression::new(self_name, method_name, args, line)));
+    }
+
+    /// 'list_literal' function match next syntax pattern:
+    /// [ [{expr},]* ]
+    fn list_literal(&mut self) -> ParseResult {
+        self.eat(Token::OpenSquareBracket)?;
+        let mut items : Vec<Box<dyn Expression>> = Vec::new();
+        self.skip_new_lines();
+        while !self.current_token_is(Token::CloseSquareBracket) {
+            items.push(self.expression()?);
+            self.skip_new_lines();
+            if !self.current_token_is(Token::CloseSquareBracket) {
+                self.eat(Token::Comma)?;
+                self.skip_new_lines();
+            }
+        }
+        self.eat(Token::CloseSquareBracket)?;
+        Ok(Box::new(ListExpression::new(items)))
+    }
+
+    /// 'map_literal' function match next syntax pattern:
+    /// { [[STRING|NAME] : {expr},]* }
+    fn map_literal(&mut self) -> ParseResult {
+        self.eat(Token::OpenCurlyBracket)?;
+        let mut items : Vec<(String, Box<dyn Expression>)> = Vec::new();
+        self.skip_new_lines();
+        while !self.current_token_is(Token::CloseCurlyBraket) {
+            let key = match self.peek_current_token() {
+                Some(Token::StringLiteral(s)) | Some(Token::Name(s)) => s,
+                Some(token) => return Err(format!("expected map key, find token {}", token)),
+                None => return Err(String::from("expected map key, found no token"))
+            };
+            self.advance();
+            self.eat(Token::Colon)?;
+            self.skip_new_lines();
+            items.push((key, self.expression()?));
+            self.skip_new_lines();
+            if !self.current_token_is(Token::CloseCurlyBraket) {
+                self.eat(Token::Comma)?;
+                self.skip_new_lines();
+            }
+        }
+        self.eat(Token::CloseCurlyBraket)?;
+        Ok(Box::new(MapExpression::new(items)))
+    }
+
     fn advance(&mut self) {
         self.pos += 1;
     }
@@ -397,53 +737,87 @@ impl Parser {
         }
     }
 
-    fn parse_func_def_args(&mut self) -> Result<Vec<String>, String> {
+    /// 'parse_func_def_args' function match next syntax pattern:
+    /// ([NAME,]* [NAME = {expr},]* [...NAME])
+    fn parse_func_def_args(&mut self) -> Result<FuncDefArgs, String> {
         self.eat(Token::OpenBracket)?;
         let mut f_args : Vec<String> = Vec::new();
-        loop {
-            if let Some(current_token) = self.peek_current_token() {
-                match current_token {
-                    Token::CloseBracket => {
-                        break;
-                    }
-                    Token::Comma => {
-                        self.advance();
-                    }
-                    Token::Name(n) => {
-                        f_args.push(n);
+        let mut f_defaults : Vec<Box<dyn Expression>> = Vec::new();
+        let mut f_rest : Option<String> = None;
+        while let Some(current_token) = self.peek_current_token() {
+            match current_token {
+                Token::CloseBracket => {
+                    break;
+                }
+                Token::Comma => {
+                    self.advance();
+                }
+                _ if f_rest.is_some() => {
+                    return Err(String::from("variadic argument must be the last argument"));
+                }
+                Token::Name(n) => {
+                    self.advance();
+                    if self.current_token_is(Token::Assignment) {
                         self.advance();
+                        f_defaults.push(self.expression()?);
+                    } else if !f_defaults.is_empty() {
+                        return Err(format!("argument '{}' without default value follows arguments with default values", n));
                     }
-                    _ => {
-                        return Err(format!("Token {} not supported in function def args", current_token.to_string()));
-                    }
+                    f_args.push(n);
+                }
+                Token::Dot if self.nth_token_is(1, Token::Dot) && self.nth_token_is(2, Token::Dot) => {
+                    self.advance();
+                    self.advance();
+                    self.advance();
+                    f_rest = Some(self.parse_name()?);
+                }
+                _ => {
+                    return Err(format!("Token {} not supported in function def args", current_token.to_string()));
                 }
             }
         }
         self.eat(Token::CloseBracket)?;
-        Ok(f_args)
+        Ok((f_args, f_defaults, f_rest))
     } 
 
     fn parse_func_call_args(&mut self) -> Result<Vec<Box<dyn Expression>>, String> {
+        let (args, kwargs) = self.parse_call_args()?;
+        if !kwargs.is_empty() {
+            return Err(String::from("keyword arguments are supported only by function calls"));
+        }
+        Ok(args)
+    }
+
+    /// parses positional arguments and keyword arguments, that follow them
+    fn parse_call_args(&mut self) -> Result<CallArgs, String> {
         self.eat(Token::OpenBracket)?;
         let mut f_args : Vec<Box<dyn Expression>> = Vec::new();
-        loop {
-            if let Some(current_token) = self.peek_current_token() {
-                match current_token {
-                    Token::CloseBracket => {
-                        break;
-                    }
-                    Token::Comma => {
-                        self.advance();
-                    }
-                    _ => {
-                        let arg_expression = self.statement()?;
-                        f_args.push(arg_expression);
-                    }
+        let mut f_kwargs : Vec<(String, Box<dyn Expression>)> = Vec::new();
+        while let Some(current_token) = self.peek_current_token() {
+            match current_token {
+                Token::CloseBracket => {
+                    break;
+                }
+                Token::Comma | Token::NewLine => {
+                    self.advance();
+                }
+                Token::Name(n) if self.nth_token_is(1, Token::Colon) => {
+                    self.advance();
+                    self.advance();
+                    self.skip_new_lines();
+                    f_kwargs.push((n, self.expression()?));
+                }
+                _ if !f_kwargs.is_empty() => {
+                    return Err(String::from("positional argument follows keyword arguments"));
+                }
+                _ => {
+                    let arg_expression = self.expression()?;
+                    f_args.push(arg_expression);
                 }
             }
         }
         self.eat(Token::CloseBracket)?;
-        Ok(f_args)
+        Ok((f_args, f_kwargs))
     }
 
     fn skip_new_lines(&mut self) {
@@ -452,4 +826,103 @@ impl Parser {
         }
     }
 
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ex_core::{tokenize, Interpreter, ValueVariant};
+    use proptest::prelude::*;
+
+    #[test]
+    fn multiple_errors_test() {
+        let program = "a = 1\n\
+                       b = \n\
+                       fn f(x) {\n\
+                           y = * 2\n\
+                           return x\n\
+                       }\n\
+                       c = (1 + 2\n\
+                       }\n\
+                       d = f(4)";
+        let (partial, errors) = Parser::new(&tokenize(program).unwrap()).parse_partial();
+        let expected = vec![
+            ParseError { message: String::from("expected value of 'b' in assignment statement"), line: 2 },
+            ParseError { message: String::from("expected expression, found MULTI TOKEN"), line: 4 },
+            ParseError { message: String::from("exprected token CLOSE BRACE TOKEN, find token NEW LINE TOKEN"), line: 7 },
+            ParseError { message: String::from("expected expression, found CLOSE CURLY BRACE TOKEN"), line: 8 },
+        ];
+        assert_eq!(errors, expected);
+
+        // statements without errors are still in the program
+        let mut interp = Interpreter::new();
+        interp.interp_expr(partial).unwrap();
+        assert_eq!(interp._get_var_value("a").unwrap(), ValueVariant::Integer(1));
+        assert_eq!(interp._get_var_value("d").unwrap(), ValueVariant::Integer(4));
+        assert!(interp._get_var_value("b").is_none());
+
+        let err = Parser::new(&tokenize(program).unwrap()).parse().err().unwrap();
+        assert_eq!(err.lines().count(), 4);
+        assert!(err.starts_with("line 2: expected value of 'b'"));
+    }
+
+    #[test]
+    fn assignment_statement_errors_test() {
+        let tests = [
+            ("[a, b] += 1", "PLUS ASSIGNMENT TOKEN expects a single name, index or field target"),
+            ("a, b -= 1", "MINUS ASSIGNMENT TOKEN expects a single name, index or field target"),
+            ("f(x) = 1", "expected assignment operator, find token OPEN BRACE TOKEN"),
+            ("1 = a", "not valid assignment target INT LITERAL TOKEN WITH VALUE: 1"),
+            ("{a: b} = m", "exprected token COMMA TOKEN, find token COLON TOKEN"),
+        ];
+        for (program, message) in tests {
+            let err = Parser::new(&tokenize(program).unwrap()).parse().err().unwrap();
+            assert_eq!(err, format!("line 1: {}", message), "program: {}", program);
+        }
+    }
+
+    #[test]
+    fn function_params_errors_test() {
+        let tests = [
+            ("fn f(a = 1, b) {\n}", "argument 'b' without default value follows arguments with default values"),
+            ("fn f(...a, b) {\n}", "variadic argument must be the last argument"),
+            ("f(a: 1, 2)", "positional argument follows keyword arguments"),
+            ("a.f(b: 1)", "keyword arguments are supported only by function calls"),
+        ];
+        for (program, message) in tests {
+            let err = Parser::new(&tokenize(program).unwrap()).parse().err().unwrap();
+            assert_eq!(err, format!("line 1: {}", message), "program: {}", program);
+        }
+    }
+
+    #[test]
+    fn end_of_input_test() {
+        for program in ["a = ", "a = 1 +", "f(1, ", "fn f(a, b", "if a {", "match a {", "match a {\n 1 | ", "x = [1, 2", "m = {a: ", "a.b("] {
+            let tokens = tokenize(program).unwrap();
+            assert!(Parser::new(&tokens).parse().is_err(), "program: {}", program);
+            let tokens_without_new_line = &tokens[..tokens.len() - 1];
+            assert!(Parser::new(&tokens_without_new_line.to_vec()).parse().is_err(), "program: {}", program);
+        }
+    }
+
+    fn any_token() -> impl Strategy<Value = Token> {
+        proptest::sample::select(vec![
+            Token::Dot, Token::Comma, Token::Colon, Token::Assignment, Token::Plus, Token::Minus,
+            Token::Multi, Token::Pow, Token::Devide, Token::OpenBracket, Token::CloseBracket,
+            Token::OpenCurlyBracket, Token::CloseCurlyBraket, Token::OpenSquareBracket, Token::CloseSquareBracket,
+            Token::StringLiteral(String::from("s")), Token::Name(String::from("a")), Token::IntLiteral(1),
+            Token::FloatLiteral(1.5), Token::Fn, Token::Return, Token::While, Token::For, Token::In,
+            Token::If, Token::Else, Token::NewLine, Token::True, Token::False, Token::Eq, Token::NotEq,
+            Token::More, Token::MoreEq, Token::Less, Token::LessEq, Token::Percent,
+            Token::PlusAssignment, Token::MinusAssignment, Token::MultiAssignment, Token::DevideAssignment, Token::PercentAssignment,
+            Token::Match, Token::Arrow, Token::Pipe, Token::Nil, Token::Coalesce, Token::Import, Token::As, Token::Exec,
+        ])
+    }
+
+    proptest! {
+        #[test]
+        fn parser_does_not_panic(tokens in proptest::collection::vec(any_token(), 0..60)) {
+            let (_, errors) = Parser::new(&tokens).parse_partial();
+            prop_assert!(errors.iter().all(|err| err.line >= 1));
+        }
+    }
+}
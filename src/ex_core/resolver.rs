@@ -0,0 +1,408 @@
+use std::collections::{HashMap, HashSet};
+
+use super::expressions::*;
+use super::bytecode::{Signature, SlotTable};
+
+/// Names, that are defined before the analysed code: by the interpreter or by previous code
+#[derive(Default)]
+pub struct Environment {
+    /// global variables, that have values
+    pub assigned_globals: HashSet<String>,
+    pub std_funcs: HashSet<String>,
+    pub user_funcs: HashMap<String, Signature>,
+    pub modules: HashSet<String>,
+}
+
+/// Result of the analysis: warnings in the order of their names in the code
+#[derive(Default, Debug)]
+pub struct Resolution {
+    pub warnings: Vec<String>,
+}
+
+struct Scope {
+    /// name of the function, None for the top level code
+    function: Option<String>,
+    /// variables of the scope in the order of their first occurrence
+    locals: SlotTable,
+    args: Vec<String>,
+    assigned: HashSet<String>,
+    read: HashSet<String>,
+    /// names, that are read, with the position of the first read
+    reads: Vec<(String, usize)>,
+}
+
+impl Scope {
+    fn new(function: Option<String>) -> Self {
+        Scope { function, locals: SlotTable::new(), args: Vec::new(), assigned: HashSet::new(), read: HashSet::new(), reads: Vec::new() }
+    }
+
+    /// suffix of warnings about names of the scope
+    fn location(&self) -> String {
+        match &self.function {
+            Some(name) => format!(" in function {}", name),
+            None => String::new(),
+        }
+    }
+}
+
+/// Semantic analysis pass, that runs between the parser and the interpreter.
+/// It warns about names, that are never assigned, unused variables of functions and calls
+/// with the wrong number of arguments, variables are bound to slots only by the compiler
+pub struct Resolver<'a> {
+    env: &'a Environment,
+    scopes: Vec<Scope>,
+    /// number of occurrences of names, that are visited
+    occurrences: usize,
+    resolution: Resolution,
+    /// user functions of the program
+    functions: HashMap<String, Signature>,
+    /// function calls with the number of positional arguments, names of keyword arguments and the location of the call
+    calls: Vec<(String, usize, Vec<String>, String)>,
+    /// read names, that are not assigned in their scope, with their location and position
+    unresolved: Vec<(String, String, usize)>,
+    imports: HashSet<String>,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn resolve(program: &dyn Expression, env: &'a Environment) -> Resolution {
+        let mut resolver = Resolver {
+            env,
+            scopes: vec![Scope::new(None)],
+            occurrences: 0,
+            resolution: Resolution::default(),
+            functions: HashMap::new(),
+            calls: Vec::new(),
+            unresolved: Vec::new(),
+            imports: HashSet::new(),
+        };
+        // the analysis doesn't fail, errors are reported by the compiler
+        let _ = program.accept(&mut resolver);
+        if let Some(scope) = resolver.scopes.pop() {
+            resolver.end_scope(scope);
+        }
+        resolver.check_names();
+        resolver.check_calls();
+        resolver.resolution
+    }
+
+    fn scope(&mut self) -> &mut Scope {
+        self.scopes.last_mut().expect("top level scope")
+    }
+
+    fn occur(&mut self, name: &str) {
+        self.occurrences += 1;
+        self.scope().locals.slot(name);
+    }
+
+    fn assign(&mut self, name: &str) {
+        self.occur(name);
+        self.scope().assigned.insert(name.to_string());
+    }
+
+    fn read(&mut self, name: &str) {
+        self.occur(name);
+        let position = self.occurrences;
+        let scope = self.scope();
+        if scope.read.insert(name.to_string()) {
+            scope.reads.push((name.to_string(), position));
+        }
+    }
+
+    fn end_scope(&mut self, scope: Scope) {
+        let location = scope.location();
+        for (name, position) in scope.reads.iter().filter(|(name, _)| !scope.assigned.contains(name)) {
+            self.unresolved.push((name.clone(), location.clone(), *position));
+        }
+        if scope.function.is_none() {
+            return;
+        }
+        // variables, which names start with '_', are unused on purpose
+        for name in scope.locals.names() {
+            if scope.assigned.contains(name) && !scope.read.contains(name) && !scope.args.contains(name) && !name.starts_with('_') {
+                self.resolution.warnings.push(format!("unused variable '{}'{}", name, location));
+            }
+        }
+    }
+
+    fn check_names(&mut self) {
+        // warnings are reported in the order of names in the code
+        self.unresolved.sort_by_key(|(_, _, position)| *position);
+        for (name, location, _) in self.unresolved.iter() {
+            let is_defined = (location.is_empty() && self.env.assigned_globals.contains(name))
+                || self.env.modules.contains(name)
+                || self.imports.contains(name);
+            if !is_defined {
+                self.resolution.warnings.push(format!("undefined name '{}'{}", name, location));
+            }
+        }
+    }
+
+    fn check_calls(&mut self) {
+        for (name, argc, keywords, location) in self.calls.iter() {
+            // std functions are called before user functions with the same name
+            if self.env.std_funcs.contains(name) {
+                continue;
+            }
+            match self.functions.get(name).or(self.env.user_funcs.get(name)) {
+                Some(signature) => if let Err(err_msg) = signature.check_args(*argc, keywords) {
+                    self.resolution.warnings.push(format!("{}{}", err_msg, location));
+                },
+                None => self.resolution.warnings.push(format!("undefined function '{}'{}", name, location)),
+            }
+        }
+    }
+
+    fn block(&mut self, body: &[Box<dyn Expression>]) -> ExpressionVisitResult {
+        for statement in body.iter() {
+            statement.accept(self)?;
+        }
+        Ok(())
+    }
+
+    /// resolves indexes of the target, the variable of the indexed target must exist
+    fn target_path(&mut self, target: &AssignmentTarget) -> ExpressionVisitResult {
+        match target {
+            AssignmentTarget::Name(name) => self.read(name),
+            AssignmentTarget::Index(object, index) => {
+                self.target_path(object)?;
+                index.accept(self)?;
+            }
+            AssignmentTarget::Field(object, _) => self.target_path(object)?,
+            AssignmentTarget::List(_) | AssignmentTarget::Map(_) => {}
+        }
+        Ok(())
+    }
+
+    fn assign_to_target(&mut self, target: &AssignmentTarget) -> ExpressionVisitResult {
+        match target {
+            AssignmentTarget::Name(name) => self.assign(name),
+            AssignmentTarget::List(targets) => {
+                for target in targets.iter() {
+                    self.assign_to_target(target)?;
+                }
+            }
+            AssignmentTarget::Map(names) => {
+                for name in names.iter() {
+                    self.assign(name);
+                }
+            }
+            AssignmentTarget::Index(..) | AssignmentTarget::Field(..) => self.target_path(target)?,
+        }
+        Ok(())
+    }
+}
+
+impl ExpressionVisitor for Resolver<'_> {
+    fn visit_float_literal_expression(&mut self, _expr: &FloatLiteralExpression) -> ExpressionVisitResult {
+        Ok(())
+    }
+
+    fn visit_int_literal_expression(&mut self, _expr: &IntLiteralExpression) -> ExpressionVisitResult {
+        Ok(())
+    }
+
+    fn visit_string_literal_expression(&mut self, _expr: &StringLiteralExpression) -> ExpressionVisitResult {
+        Ok(())
+    }
+
+    fn visit_bool_literal_expression(&mut self, _expr: &BoolLiteralExpression) -> ExpressionVisitResult {
+        Ok(())
+    }
+
+    fn visit_nil_literal_expression(&mut self, _expr: &NilLiteralExpression) -> ExpressionVisitResult {
+        Ok(())
+    }
+
+    fn visit_list_expression(&mut self, expr: &ListExpression) -> ExpressionVisitResult {
+        self.block(&expr.items)
+    }
+
+    fn visit_map_expression(&mut self, expr: &MapExpression) -> ExpressionVisitResult {
+        for (_, item) in expr.items.iter() {
+            item.accept(self)?;
+        }
+        Ok(())
+    }
+
+    fn visit_name_expression(&mut self, expr: &NameExpression) -> ExpressionVisitResult {
+        self.read(&expr.name);
+        Ok(())
+    }
+
+    fn visit_unary_expression(&mut self, expr: &UnaryExpression) -> ExpressionVisitResult {
+        expr.expr.accept(self)
+    }
+
+    fn visit_binary_expression(&mut self, expr: &BinaryExpression) -> ExpressionVisitResult {
+        expr.left.accept(self)?;
+        expr.right.accept(self)
+    }
+
+    fn visit_index_expression(&mut self, expr: &IndexExpression) -> ExpressionVisitResult {
+        expr.object.accept(self)?;
+        expr.index.accept(self)
+    }
+
+    fn visit_field_expression(&mut self, expr: &FieldExpression) -> ExpressionVisitResult {
+        expr.object.accept(self)
+    }
+
+    fn visit_assignment_expression(&mut self, expr: &AssignmentExpression) -> ExpressionVisitResult {
+        expr.value.accept(self)?;
+        self.assign(&expr.name);
+        Ok(())
+    }
+
+    fn visit_compound_assignment_expression(&mut self, expr: &CompoundAssignmentExpression) -> ExpressionVisitResult {
+        self.target_path(&expr.target)?;
+        expr.value.accept(self)
+    }
+
+    fn visit_multiple_assignment_expression(&mut self, expr: &MultipleAssignmentExpression) -> ExpressionVisitResult {
+        self.block(&expr.values)?;
+        for target in expr.targets.iter() {
+            self.assign_to_target(target)?;
+        }
+        Ok(())
+    }
+
+    fn visit_if_expression(&mut self, expr: &IfExpression) -> ExpressionVisitResult {
+        expr.if_expr.accept(self)?;
+        self.block(&expr.true_expression)?;
+        self.block(&expr.false_expression)
+    }
+
+    fn visit_match_expression(&mut self, expr: &MatchExpression) -> ExpressionVisitResult {
+        expr.value.accept(self)?;
+        for arm in expr.arms.iter() {
+            for pattern in arm.patterns.iter() {
+                match pattern {
+                    MatchPattern::Wildcard => {}
+                    MatchPattern::Binding(name) => self.assign(name),
+                    MatchPattern::Value(value) => value.accept(self)?,
+                    MatchPattern::Range { low, high, .. } => {
+                        low.accept(self)?;
+                        high.accept(self)?;
+                    }
+                }
+            }
+            if let Some(guard) = &arm.guard {
+                guard.accept(self)?;
+            }
+            self.block(&arm.body)?;
+        }
+        Ok(())
+    }
+
+    fn visit_while_expression(&mut self, expr: &WhileExpression) -> ExpressionVisitResult {
+        expr.while_expr.accept(self)?;
+        self.block(&expr.body_exprs)
+    }
+
+    fn visit_for_expression(&mut self, expr: &ForExpression) -> ExpressionVisitResult {
+        expr.l_bound.accept(self)?;
+        expr.r_bound.accept(self)?;
+        self.assign(&expr.var_name);
+        self.block(&expr.body_exprs)
+    }
+
+    fn visit_function_def_expression(&mut self, expr: &FunctionDefExpression) -> ExpressionVisitResult {
+        let signature = Signature {
+            name: expr.name.clone(),
+            args: expr.args.clone(),
+            required: expr.args.len() - expr.defaults.len(),
+            rest: expr.rest.clone(),
+        };
+        self.functions.insert(expr.name.clone(), signature);
+        self.scopes.push(Scope::new(Some(expr.name.clone())));
+        self.scope().args = expr.args.iter().chain(expr.rest.iter()).cloned().collect();
+        for arg in expr.args.iter().chain(expr.rest.iter()) {
+            self.assign(arg);
+        }
+        // default values are evaluated in the scope of the function
+        let result = self.block(&expr.defaults).and_then(|_| self.block(&expr.body));
+        if let Some(scope) = self.scopes.pop() {
+            self.end_scope(scope);
+        }
+        result
+    }
+
+    fn visit_function_call_expression(&mut self, expr: &FunctionCallExpression) -> ExpressionVisitResult {
+        self.block(&expr.args)?;
+        for (_, value) in expr.kwargs.iter() {
+            value.accept(self)?;
+        }
+        let keywords = expr.kwargs.iter().map(|(keyword, _)| keyword.clone()).collect();
+        let location = self.scope().location();
+        self.calls.push((expr.name.clone(), expr.args.len(), keywords, location));
+        Ok(())
+    }
+
+    fn visit_method_call_expression(&mut self, expr: &MethodCallExpression) -> ExpressionVisitResult {
+        self.block(&expr.args)?;
+        self.read(&expr.self_name);
+        Ok(())
+    }
+
+    fn visit_anonymous_method_call_expression(&mut self, expr: &AnonymousMethodExpression) -> ExpressionVisitResult {
+        expr.self_expr.accept(self)?;
+        self.block(&expr.args)
+    }
+
+    fn visit_return_expression(&mut self, expr: &ReturnExpression) -> ExpressionVisitResult {
+        expr.expr.accept(self)
+    }
+
+    fn visit_import_expression(&mut self, expr: &ImportExpression) -> ExpressionVisitResult {
+        self.imports.insert(expr.namespace.clone());
+        Ok(())
+    }
+
+    fn visit_statement_list_expression(&mut self, expr: &StatementListExpression) -> ExpressionVisitResult {
+        self.block(&expr.statement_list)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ex_core::{tokenize, Parser};
+
+    fn resolve(program: &str, env: &Environment) -> Resolution {
+        let expr = Parser::new(&tokenize(program).unwrap()).parse().unwrap();
+        Resolver::resolve(expr.as_ref(), env)
+    }
+
+    #[test]
+    fn undefined_name_test() {
+        let program = "count = 0\nif count > 1 {\n writeln(coutn)\n}\nfn f() {\n return count\n}\nwhile count < 3 {\n count += 1\n}";
+        let mut env = Environment::default();
+        env.std_funcs.insert(String::from("writeln"));
+        assert_eq!(resolve(program, &env).warnings, vec!["undefined name 'coutn'", "undefined name 'count' in function f"]);
+
+        // names, that are assigned later in the scope, module namespaces and globals of the interpreter are defined
+        let program = "import \"utils.ex\" as u\nwhile true {\n if x > 0 {\n writeln(u.name, args)\n }\n x = 1\n}";
+        env.assigned_globals.insert(String::from("args"));
+        assert_eq!(resolve(program, &env).warnings, Vec::<String>::new());
+    }
+
+    #[test]
+    fn unused_variable_test() {
+        let program = "fn f(a, b) {\n x = a\n y = 1\n _z = 2\n y += 1\n [c, d] = [1, 2]\n return c\n}\nunused_global = 1";
+        assert_eq!(resolve(program, &Environment::default()).warnings, vec!["unused variable 'x' in function f", "unused variable 'd' in function f"]);
+    }
+
+    #[test]
+    fn function_calls_test() {
+        let program = "fn f(a, b) {\n return g(a)\n}\nfn g(a) {\n return a\n}\nf(1)\nh()\nlen(1, 2)\nk(1)\nf(1, c: 2)";
+        let mut env = Environment::default();
+        env.std_funcs.insert(String::from("len"));
+        env.user_funcs.insert(String::from("k"), Signature { name: String::from("k"), args: vec![String::from("a"), String::from("b")], required: 2, rest: None });
+        assert_eq!(resolve(program, &env).warnings, vec![
+            "function f expects 2 arguments, got 1",
+            "undefined function 'h'",
+            "function k expects 2 arguments, got 1",
+            "function f has no argument 'c'",
+        ]);
+    }
+}
@@ -1,37 +1,41 @@
 use std::fmt;
 
+/// Error of the lexical analysis with position of the not valid character,
+/// line and column are counted from 1
+#[derive(Clone, PartialEq, Debug)]
+pub struct LexError {
+    pub message: String,
+    pub line: usize,
+    pub col: usize,
+}
 
-pub fn tokenize(program: &String) -> Vec<Token> {
-    let mut result : Vec<Token> = Vec::new();
-    for line in program.lines().clone() {
-        let mut word_string = line.to_string();
-        while !word_string.is_empty() {
-            if let Some(token) = read_token_from_char(&mut word_string) {
-                result.push(token)
-            } else if let Some(token) = read_token_from_string(&mut word_string) {
-                result.push(token)    
-            } else {
-                if word_string.chars().next().unwrap() == ' ' {
-                    word_string.remove(0);
-                } else {
-                    break;
-                }
-            }
-        }
-        result.push(Token::NewLine);
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.col, self.message)
     }
-    return result;
+}
+
+pub fn tokenize(program: &str) -> Result<Vec<Token>, LexError> {
+    Lexer::new(program).tokenize()
 }
 
 #[derive(Clone, PartialEq, Debug)]
 pub enum Token {
     Dot,
     Comma,
+    Colon,
     Assignment,
     Plus,
     Minus,
     Multi,
+    Pow,
     Devide,
+    Percent,
+    PlusAssignment,
+    MinusAssignment,
+    MultiAssignment,
+    DevideAssignment,
+    PercentAssignment,
     OpenBracket,
     CloseBracket,
     OpenCurlyBracket,
@@ -50,6 +54,13 @@ pub enum Token {
     In,
     If,
     Else,
+    Match,
+    Import,
+    As,
+    Nil,
+    Coalesce,
+    Arrow,
+    Pipe,
     NewLine, 
     True,
     False,
@@ -66,6 +77,7 @@ pub enum Token {
         match self {
             Token::Dot => write!(f, "DOT TOKEN"),
             Token::Comma => write!(f, "COMMA TOKEN"),
+            Token::Colon => write!(f, "COLON TOKEN"),
             Token::Assignment => write!(f, "ASSIGNMENT TOKEN"),
             Token::StringLiteral(s) => write!(f, "STRING LITERAL TOKEN WITH VALUE: {}", s),
             Token::IntLiteral(n) => write!(f, "INT LITERAL TOKEN WITH VALUE: {}", n),
@@ -78,6 +90,20 @@ pub enum Token {
             Token::Plus => write!(f, "PLUS TOKEN"),
             Token::Minus => write!(f, "MINUS TOKEN"),
             Token::Multi => write!(f, "MULTI TOKEN"),
+            Token::Pow => write!(f, "POW TOKEN"),
+            Token::Match => write!(f, "MATCH TOKEN"),
+            Token::Import => write!(f, "IMPORT TOKEN"),
+            Token::As => write!(f, "AS TOKEN"),
+            Token::Nil => write!(f, "NIL TOKEN"),
+            Token::Coalesce => write!(f, "COALESCE TOKEN"),
+            Token::Arrow => write!(f, "ARROW TOKEN"),
+            Token::Pipe => write!(f, "PIPE TOKEN"),
+            Token::Percent => write!(f, "PERCENT TOKEN"),
+            Token::PlusAssignment => write!(f, "PLUS ASSIGNMENT TOKEN"),
+            Token::MinusAssignment => write!(f, "MINUS ASSIGNMENT TOKEN"),
+            Token::MultiAssignment => write!(f, "MULTI ASSIGNMENT TOKEN"),
+            Token::DevideAssignment => write!(f, "DEVIDE ASSIGNMENT TOKEN"),
+            Token::PercentAssignment => write!(f, "PERCENT ASSIGNMENT TOKEN"),
             Token::Devide => write!(f, "DEVIDE TOKEN"),
             Token::Name(s) => write!(f, "NAME TOKEN {s}"), 
             Token::NewLine => write!(f, "NEW LINE TOKEN"),
@@ -115,182 +141,281 @@ fn char_to_simple_token(ch : char) -> Option<Token> {
         '-' =>      Some(Token::Minus),
         '/' =>      Some(Token::Devide),
         '*' =>      Some(Token::Multi),
+        '%' =>      Some(Token::Percent),
         ',' =>      Some(Token::Comma),
+        ':' =>      Some(Token::Colon),
+        '|' =>      Some(Token::Pipe),
         _ =>        None
     }
 }
 
-fn read_token_from_char(source: &mut String) -> Option<Token> {
-    let mut chars = source.chars();
-    if let Some(f_ch) = chars.next() {
-        if let Some(token) = char_to_simple_token(f_ch) {
-            source.replace_range(0..1, "");
-            return Some(token)
-        } else if f_ch == '=' {
-            if let Some(s_ch) = chars.next() {
-                if s_ch != '=' {
-                    source.replace_range(0..1, "");
-                    return Some(Token::Assignment);    
-                }
-            }
-        } else if f_ch == '>' {
-            if let Some(s_ch) = chars.next() {
-                if s_ch != '=' {
-                    source.replace_range(0..1, "");
-                    return Some(Token::More);    
-                }
-            }
-        } else if f_ch == '<' {
-            if let Some(s_ch) = chars.next() {
-                if s_ch != '=' {
-                    source.replace_range(0..1, "");
-                    return Some(Token::Less);    
-                }
-            }
-        }
+/// compound assignment operator is the binary operator char followed by '='
+fn compound_assignment_token(ch : char) -> Option<Token> {
+    match ch {
+        '+' =>      Some(Token::PlusAssignment),
+        '-' =>      Some(Token::MinusAssignment),
+        '*' =>      Some(Token::MultiAssignment),
+        '/' =>      Some(Token::DevideAssignment),
+        '%' =>      Some(Token::PercentAssignment),
+        _ =>        None
     }
-    None
 }
 
-fn read_token_from_string(source: &mut String) -> Option<Token> {
-    if source.is_empty() {
-        return None
+fn keyword_to_token(word: &str) -> Option<Token> {
+    match word {
+        "fn" =>     Some(Token::Fn),
+        "while" =>  Some(Token::While),
+        "for" =>    Some(Token::For),
+        "in" =>     Some(Token::In),
+        "if" =>     Some(Token::If),
+        "else" =>   Some(Token::Else),
+        "match" =>  Some(Token::Match),
+        "import" => Some(Token::Import),
+        "as" =>     Some(Token::As),
+        "nil" =>    Some(Token::Nil),
+        "return" => Some(Token::Return),
+        "true" =>   Some(Token::True),
+        "false" =>  Some(Token::False),
+        _ =>        None
     }
-    if let Some(token) = read_number_token(source) {
-        return Some(token)
-    } else if let Some(token) = read_string_literal_token(source) {
-        return Some(token);
-    } else if let Some(token) = read_reserved_token(source) {
-        return Some(token);
-    } 
-    else if let Some(token) = read_name_token(source) {
-        return Some(token);
-    } 
-    None
 }
 
-fn read_number_token(source: &mut String) -> Option<Token> {
-    let mut result_string = String::new();
-    for (i, ch) in source.char_indices() {
-        if i == 0 {
-            if ch != '-' && ch != '+' && !ch.is_numeric() {
-                return None
-            }
-            result_string.push(ch);
-        } else {
-            if ch.is_numeric() || ch == '.' {
-                result_string.push(ch);
-            } else {
-                break;
-            }    
-        }
+fn operator_to_token(first: char, second: Option<char>) -> Option<Token> {
+    match (first, second) {
+        ('=', Some('=')) => Some(Token::Eq),
+        ('!', Some('=')) => Some(Token::NotEq),
+        ('>', Some('=')) => Some(Token::MoreEq),
+        ('<', Some('=')) => Some(Token::LessEq),
+        ('=', Some('>')) => Some(Token::Arrow),
+        ('=', _) =>         Some(Token::Assignment),
+        ('>', _) =>         Some(Token::More),
+        ('<', _) =>         Some(Token::Less),
+        _ =>                None
     }
-    if result_string.is_empty() {
-        return None
+}
+
+/// only ASCII digits are allowed in names and numbers
+fn is_name_char(ch: char) -> bool {
+    ch.is_alphabetic() || ch.is_ascii_digit() || ch == '_'
+}
+
+/// Single pass lexer, that moves a cursor over the source text
+/// and reads every token with a lookahead of a few chars
+pub struct Lexer<'a> {
+    source: &'a str,
+    pos: usize,
+    line: usize,
+    col: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Lexer {
+            source,
+            pos: 0,
+            line: 1,
+            col: 1
+        }
     }
-    if let Some(dot_pos) = result_string.find('.') {
-        if dot_pos == result_string.len() - 1 {
-            if let Ok(i) = result_string[..dot_pos].parse::<i64>() {
-                source.replace_range(0..result_string.len() - 1, "");
-                return Some(Token::IntLiteral(i));
-            }
+
+    pub fn tokenize(&mut self) -> Result<Vec<Token>, LexError> {
+        let mut result : Vec<Token> = Vec::new();
+        while let Some(token) = self.next_token()? {
+            result.push(token);
         }
-        if let Ok(f) = result_string.parse::<f64>() {
-            source.replace_range(0..result_string.len(), "");
-            return Some(Token::FloatLiteral(f));
+        // the last line is terminated even if there is no line break at the end
+        if !self.source.is_empty() && !self.source.ends_with('\n') {
+            result.push(Token::NewLine);
         }
-    } else if let Ok(i) = result_string.parse::<i64>() {
-        source.replace_range(0..result_string.len(), "");
-        return Some(Token::IntLiteral(i));
+        Ok(result)
     }
-    None
-}
 
+    fn peek(&self) -> Option<char> {
+        self.source[self.pos..].chars().next()
+    }
 
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.source[self.pos..].chars().nth(n)
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.pos += ch.len_utf8();
+        if ch == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+        Some(ch)
+    }
 
-fn read_reserved_token(source: &mut String) -> Option<Token> { 
-    if try_read_reserved_word("fn", source) {
-        return Some(Token::Fn);
-    } else if try_read_reserved_word("while", source) {
-        return Some(Token::While);
-    } else if try_read_reserved_word("for", source) {
-        return Some(Token::For);
-    }  else if try_read_reserved_word("in", source) {
-        return Some(Token::In);
-    } else if try_read_reserved_word("if", source) {
-        return Some(Token::If);
-    }  else if try_read_reserved_word("else", source) {
-        return Some(Token::Else);
-    } else if try_read_reserved_word("return", source) {
-        return Some(Token::Return);
-    } else if try_read_reserved_word("true", source) {
-        return Some(Token::True);
-    } else if try_read_reserved_word("false", source) {
-        return Some(Token::False);
-    } else if try_read_reserved_word("==", source) {
-        return Some(Token::Eq);
-    } else if try_read_reserved_word("!=", source) {
-        return Some(Token::NotEq);
-    } else if try_read_reserved_word(">=", source) {
-        return Some(Token::MoreEq);
-    } else if try_read_reserved_word("<=", source) {
-        return Some(Token::LessEq);
+    fn read_while(&mut self, pred: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        while self.peek().is_some_and(&pred) {
+            self.advance();
+        }
+        &self.source[start..self.pos]
     }
 
+    fn error(&self, message: String) -> LexError {
+        LexError { message, line: self.line, col: self.col }
+    }
 
-    None
-}
+    /// skips whitespaces and the comment, that starts with "//" and ends at the line end
+    fn skip_whitespaces(&mut self) {
+        self.read_while(|ch| ch.is_whitespace() && ch != '\n');
+        if self.peek() == Some('/') && self.peek_nth(1) == Some('/') {
+            self.read_while(|ch| ch != '\n');
+        }
+    }
 
-fn try_read_reserved_word(reserved: &str, source: &mut String) -> bool {
-    let reserved_with_space = reserved.to_owned() + " ";
-    
-    let reserved_with_open_brace = reserved.to_owned() + "(";
-    let reserved_with_close_brace = reserved.to_owned() + ")";
-
-    let reserved_with_open_square_brace = reserved.to_owned() + "[";
-    let reserved_with_close_square_brace = reserved.to_owned() + "]";
-
-    if source == reserved 
-    || source.starts_with(&reserved_with_space) 
-    || source.starts_with(&reserved_with_open_brace) 
-    || source.starts_with(&reserved_with_close_brace) 
-    || source.starts_with(&reserved_with_open_square_brace) 
-    || source.starts_with(&reserved_with_close_square_brace) {
-        source.replace_range(..reserved.len(), "");
-        return true;
+    fn next_token(&mut self) -> Result<Option<Token>, LexError> {
+        self.skip_whitespaces();
+        let Some(ch) = self.peek() else {
+            return Ok(None);
+        };
+        if ch == '\n' {
+            self.advance();
+            return Ok(Some(Token::NewLine));
+        }
+        if ch == '*' && self.peek_nth(1) == Some('*') {
+            self.advance();
+            self.advance();
+            return Ok(Some(Token::Pow));
+        }
+        if ch == '?' && self.peek_nth(1) == Some('?') {
+            self.advance();
+            self.advance();
+            return Ok(Some(Token::Coalesce));
+        }
+        if self.peek_nth(1) == Some('=') {
+            if let Some(token) = compound_assignment_token(ch) {
+                self.advance();
+                self.advance();
+                return Ok(Some(token));
+            }
+        }
+        if let Some(token) = char_to_simple_token(ch) {
+            self.advance();
+            return Ok(Some(token));
+        }
+        if ch.is_ascii_digit() {
+            return self.read_number().map(Some);
+        }
+        if ch == '"' {
+            return self.read_string_literal().map(Some);
+        }
+        if is_name_char(ch) {
+            return Ok(Some(self.read_name_or_keyword()));
+        }
+        if matches!(ch, '=' | '!' | '>' | '<') {
+            return self.read_operator().map(Some);
+        }
+        Err(self.error(format!("unexpected character '{}'", ch)))
     }
-    return false;
-} 
-
-// TODO: think about reading everything untill escape symbol??
-fn read_name_token(source: &mut String) -> Option<Token> {
-    let mut result = String::new();
-    for ch in source.chars() {
-        if ch.is_alphanumeric() || ch == '_' {
-            result.push(ch);
+
+    /// reads decimal integer or float literal with optional fraction and exponent,
+    /// or integer literal with 0x, 0o or 0b prefix, digits can be separated by underscores
+    fn read_number(&mut self) -> Result<Token, LexError> {
+        let (line, col) = (self.line, self.col);
+        let start = self.pos;
+        let radix = match (self.peek(), self.peek_nth(1)) {
+            (Some('0'), Some('x' | 'X')) => 16,
+            (Some('0'), Some('o' | 'O')) => 8,
+            (Some('0'), Some('b' | 'B')) => 2,
+            _ => 10
+        };
+        let mut is_float = false;
+        if radix != 10 {
+            self.advance();
+            self.advance();
+            self.read_while(|ch| ch.is_ascii_alphanumeric() || ch == '_');
         } else {
-            break;
+            self.read_while(|ch| ch.is_ascii_digit() || ch == '_');
+            // dot that is not followed by a digit is a method call, like 2.pow(2)
+            if self.peek() == Some('.') && self.peek_nth(1).is_some_and(|ch| ch.is_ascii_digit()) {
+                is_float = true;
+                self.advance();
+                self.read_while(|ch| ch.is_ascii_digit() || ch == '_');
+            }
+            if matches!(self.peek(), Some('e' | 'E')) {
+                let digit_pos = if matches!(self.peek_nth(1), Some('+' | '-')) { 2 } else { 1 };
+                if self.peek_nth(digit_pos).is_some_and(|ch| ch.is_ascii_digit()) {
+                    is_float = true;
+                    for _ in 0..digit_pos {
+                        self.advance();
+                    }
+                    self.read_while(|ch| ch.is_ascii_digit() || ch == '_');
+                }
+            }
         }
+        // number must not be glued with a name or another number, like 12abc or 1.2.3
+        let glued = self.peek().is_some_and(is_name_char)
+            || (self.peek() == Some('.') && self.peek_nth(1).is_some_and(|ch| ch.is_ascii_digit()));
+        if glued {
+            self.read_while(|ch| is_name_char(ch) || ch == '.');
+        }
+        let literal = &self.source[start..self.pos];
+        let error = |reason: &str| LexError { message: format!("not valid number literal '{}': {}", literal, reason), line, col };
+        if glued {
+            return Err(error("unexpected characters after the number"));
+        }
+        let digits = if radix == 10 { literal } else { &literal[2..] };
+        if digits.is_empty() {
+            return Err(error("no digits after the prefix"));
+        }
+        let chars: Vec<char> = digits.chars().collect();
+        for (i, ch) in chars.iter().enumerate() {
+            if *ch == '_' {
+                let is_digit = |pos: Option<&char>| pos.is_some_and(|ch| ch.is_digit(radix));
+                if i == 0 || !is_digit(chars.get(i - 1)) || !is_digit(chars.get(i + 1)) {
+                    return Err(error("underscore must be between digits"));
+                }
+            } else if radix != 10 && !ch.is_digit(radix) {
+                return Err(error(&format!("'{}' is not a digit in base {}", ch, radix)));
+            }
+        }
+        let digits = digits.replace('_', "");
+        if is_float {
+            return digits.parse::<f64>().map(Token::FloatLiteral).map_err(|_| error("not valid float"));
+        }
+        i64::from_str_radix(&digits, radix).map(Token::IntLiteral).map_err(|_| error("integer is too large"))
     }
-    if result.is_empty() {
-        return None;
+
+    fn read_string_literal(&mut self) -> Result<Token, LexError> {
+        let (line, col) = (self.line, self.col);
+        self.advance();
+        let literal = self.read_while(|ch| ch != '"' && ch != '\n');
+        if self.peek() != Some('"') {
+            return Err(LexError { message: String::from("unterminated string literal"), line, col });
+        }
+        self.advance();
+        Ok(Token::StringLiteral(literal.to_string()))
     }
-    source.replace_range(0..result.len(), "");
-    return Some(Token::Name(result));
-}
 
-fn read_string_literal_token(source: &mut String) -> Option<Token> {
-    if !source.starts_with("\"") {
-        return None;
+    /// the whole identifier is read first, so keyword is recognized only at the identifier boundary,
+    /// e.g. 'if{' is a keyword and 'iffy' is a name
+    fn read_name_or_keyword(&mut self) -> Token {
+        let word = self.read_while(is_name_char);
+        keyword_to_token(word).unwrap_or_else(|| Token::Name(word.to_string()))
     }
-    let quote_indexes : Vec<_> = source.match_indices("\"").collect();
-    if quote_indexes.len() < 2 {
-        return None;
+
+    /// the longest operator is taken, e.g. 'a==b' is 'a', '==', 'b'
+    fn read_operator(&mut self) -> Result<Token, LexError> {
+        let ch = self.peek().unwrap_or_default();
+        let next = self.peek_nth(1);
+        match operator_to_token(ch, next) {
+            Some(token) => {
+                let len = if matches!(token, Token::Eq | Token::NotEq | Token::MoreEq | Token::LessEq | Token::Arrow) { 2 } else { 1 };
+                for _ in 0..len {
+                    self.advance();
+                }
+                Ok(token)
+            }
+            None => Err(self.error(format!("unexpected character '{}'", ch)))
+        }
     }
-    let end_literal = quote_indexes[1].0;
-    let string_literal = source[1..end_literal].to_string();
-    source.replace_range(..end_literal + 1, "");
-    return Some(Token::StringLiteral(string_literal));
 }
 
 #[cfg(test)]
@@ -302,7 +427,7 @@ mod tests {
     fn bug_test() {
 
         let program = "write(\"input: \", a)".to_string();
-        let tokens = tokenize(&program);
+        let tokens = tokenize(&program).unwrap();
         for token in tokens.iter() {
             println!("token is {}", token);
         }
@@ -314,8 +439,8 @@ mod tests {
     fn tokenizer_test() {
         let program: String = String::from("x = 10 \n\
                                             y = 20.5 \n\
-                                            s = \"str\" \\
-                                            x = x * ( x + y ) \\
+                                            s = \"str\" \n\
+                                            x = x * ( x + y ) \n\
                                             a = true \n\
                                             c = a == true\n\
                                             bu = c != false \n\
@@ -450,7 +575,7 @@ mod tests {
             Token::CloseBracket,
             Token::NewLine
         ];
-        let tokens = tokenize(&program);
+        let tokens = tokenize(&program).unwrap();
         assert_eq!(tokens, expected_tokens);
     }
 
@@ -467,7 +592,7 @@ mod tests {
             Token::FloatLiteral(0.5),
             Token::NewLine
         ];
-        let tokens1 = tokenize(&program1);
+        let tokens1 = tokenize(&program1).unwrap();
         assert_eq!(tokens1, expected_tokens1);
 
 
@@ -481,8 +606,46 @@ mod tests {
             Token::CloseBracket,
             Token::NewLine
         ];
-        let tokens2 = tokenize(&program2);
+        let tokens2 = tokenize(&program2).unwrap();
         assert_eq!(tokens2, expected_tokens2);
+
+        let program3 = String::from("0x1F 0o755 0b1010 1_000_000 1e-9 2.5E3 0xff_ff 1.");
+        let expected_tokens3 = vec![
+            Token::IntLiteral(31),
+            Token::IntLiteral(493),
+            Token::IntLiteral(10),
+            Token::IntLiteral(1_000_000),
+            Token::FloatLiteral(1e-9),
+            Token::FloatLiteral(2500.0),
+            Token::IntLiteral(0xffff),
+            Token::IntLiteral(1),
+            Token::Dot,
+            Token::NewLine
+        ];
+        let tokens3 = tokenize(&program3).unwrap();
+        assert_eq!(tokens3, expected_tokens3);
+    }
+
+    #[test]
+    fn malformed_number_test() {
+        let tests = [
+            ("1.2.3", "unexpected characters after the number"),
+            ("12abc", "unexpected characters after the number"),
+            ("1e", "unexpected characters after the number"),
+            ("0x", "no digits after the prefix"),
+            ("0b102", "'2' is not a digit in base 2"),
+            ("0o78", "'8' is not a digit in base 8"),
+            ("1__000", "underscore must be between digits"),
+            ("1_", "underscore must be between digits"),
+            ("0x_1", "underscore must be between digits"),
+            ("1_.5", "underscore must be between digits"),
+            ("99999999999999999999", "integer is too large"),
+        ];
+        for (literal, reason) in tests {
+            let err = tokenize(&format!("x = {}", literal)).unwrap_err();
+            let message = format!("not valid number literal '{}': {}", literal, reason);
+            assert_eq!(err, LexError { message, line: 1, col: 5 });
+        }
     }
 
     #[test]
@@ -497,9 +660,161 @@ mod tests {
             Token::CloseBracket,
             Token::NewLine,
         ];
-        let tokens = tokenize(&program);
+        let tokens = tokenize(&program).unwrap();
         assert_eq!(tokens, expected_tokens);  
     }
 
 
-}
\ No newline at end of file
+    #[test]
+    fn unknown_char_test() {
+        let tests = [
+            ("a = !b", 1, 5, "unexpected character '!'"),
+            ("a = 1\nb = a $ 2", 2, 7, "unexpected character '$'"),
+            ("a = 1;", 1, 6, "unexpected character ';'"),
+            ("x = 1\n  y = x ¿", 2, 9, "unexpected character '¿'"),
+            ("s = \"abc\nb = 1", 1, 5, "unterminated string literal"),
+            ("a = 1.2.3", 1, 5, "not valid number literal '1.2.3': unexpected characters after the number"),
+            ("a = ٣", 1, 5, "unexpected character '٣'"),
+        ];
+        for (program, line, col, message) in tests {
+            let err = tokenize(program).unwrap_err();
+            assert_eq!(err, LexError { message: message.to_string(), line, col }, "program: {}", program);
+        }
+    }
+
+    #[test]
+    fn comment_test() {
+        let program = "// comment\na = 1 // a { comment\nb = a / 2 //\ns = \"//\"\n  // last";
+        let name = |s: &str| Token::Name(String::from(s));
+        assert_eq!(tokenize(program).unwrap(), vec![
+            Token::NewLine,
+            name("a"), Token::Assignment, Token::IntLiteral(1), Token::NewLine,
+            name("b"), Token::Assignment, name("a"), Token::Devide, Token::IntLiteral(2), Token::NewLine,
+            name("s"), Token::Assignment, Token::StringLiteral(String::from("//")), Token::NewLine,
+            // the last line is terminated after the comment
+            Token::NewLine,
+        ]);
+    }
+
+    /// run with 'cargo test --release -- --ignored tokenizer_benchmark --nocapture'
+    #[test]
+    #[ignore]
+    fn tokenizer_benchmark() {
+        let line = "value = some_func(10, 20.5, \"str\") * (x + y) >= 3 == true\n";
+        let time_for = |lines: usize| {
+            let program = line.repeat(lines);
+            let start = std::time::Instant::now();
+            let tokens = tokenize(&program).unwrap();
+            let elapsed = start.elapsed();
+            println!("{} lines, {} tokens: {:?}", lines, tokens.len(), elapsed);
+            elapsed.as_secs_f64()
+        };
+        let small = time_for(25_000);
+        let large = time_for(100_000);
+        // four times more input should take about four times more time
+        assert!(large / small < 8.0, "tokenizer time grows faster than input: {} vs {}", small, large);
+    }
+
+
+    #[test]
+    fn identifier_boundary_test() {
+        let tests = vec![
+            ("if{", vec![Token::If, Token::OpenCurlyBracket]),
+            ("}else{", vec![Token::CloseCurlyBraket, Token::Else, Token::OpenCurlyBracket]),
+            ("iffy = fn_a", vec![Token::Name(String::from("iffy")), Token::Assignment, Token::Name(String::from("fn_a"))]),
+            ("a==b", vec![Token::Name(String::from("a")), Token::Eq, Token::Name(String::from("b"))]),
+            ("a!=b", vec![Token::Name(String::from("a")), Token::NotEq, Token::Name(String::from("b"))]),
+            ("2**-x", vec![Token::IntLiteral(2), Token::Pow, Token::Minus, Token::Name(String::from("x"))]),
+            ("1=>a|b", vec![Token::IntLiteral(1), Token::Arrow, Token::Name(String::from("a")), Token::Pipe, Token::Name(String::from("b"))]),
+            ("a??nil", vec![Token::Name(String::from("a")), Token::Coalesce, Token::Nil]),
+            ("a+=1", vec![Token::Name(String::from("a")), Token::PlusAssignment, Token::IntLiteral(1)]),
+            ("a%=b%2", vec![Token::Name(String::from("a")), Token::PercentAssignment, Token::Name(String::from("b")), Token::Percent, Token::IntLiteral(2)]),
+            ("a<=-1", vec![Token::Name(String::from("a")), Token::LessEq, Token::Minus, Token::IntLiteral(1)]),
+            ("x-1", vec![Token::Name(String::from("x")), Token::Minus, Token::IntLiteral(1)]),
+            ("return-x", vec![Token::Return, Token::Minus, Token::Name(String::from("x"))]),
+            ("import utils as u", vec![Token::Import, Token::Name(String::from("utils")), Token::As, Token::Name(String::from("u"))]),
+            ("assert = imports", vec![Token::Name(String::from("assert")), Token::Assignment, Token::Name(String::from("imports"))]),
+        ];
+        for (program, mut expected) in tests {
+            expected.push(Token::NewLine);
+            assert_eq!(tokenize(program).unwrap(), expected, "program: {}", program);
+        }
+    }
+
+    mod whitespace_props {
+        use super::super::*;
+        use proptest::prelude::*;
+
+        fn simple_tokens() -> Vec<(Token, &'static str)> {
+            vec![
+                (Token::Dot, "."), (Token::OpenBracket, "("), (Token::CloseBracket, ")"),
+                (Token::OpenCurlyBracket, "{"), (Token::CloseCurlyBraket, "}"),
+                (Token::OpenSquareBracket, "["), (Token::CloseSquareBracket, "]"),
+                (Token::Plus, "+"), (Token::Minus, "-"), (Token::Devide, "/"), (Token::Multi, "*"), (Token::Pow, "**"), (Token::Percent, "%"),
+                (Token::Comma, ","), (Token::Colon, ":"), (Token::Assignment, "="),
+                (Token::Eq, "=="), (Token::NotEq, "!="), (Token::More, ">"), (Token::MoreEq, ">="),
+                (Token::Less, "<"), (Token::LessEq, "<="), (Token::NewLine, "\n"),
+                (Token::PlusAssignment, "+="), (Token::MinusAssignment, "-="), (Token::MultiAssignment, "*="),
+                (Token::DevideAssignment, "/="), (Token::PercentAssignment, "%="),
+                (Token::Fn, "fn"), (Token::While, "while"), (Token::For, "for"), (Token::In, "in"),
+                (Token::If, "if"), (Token::Else, "else"), (Token::Return, "return"),
+                (Token::True, "true"), (Token::False, "false"), (Token::Match, "match"), (Token::Nil, "nil"),
+                (Token::Import, "import"), (Token::As, "as"),
+                (Token::Coalesce, "??"),
+                (Token::Arrow, "=>"), (Token::Pipe, "|"),
+            ]
+        }
+
+        /// token with its source text
+        fn token() -> impl Strategy<Value = (Token, String)> {
+            prop_oneof![
+                proptest::sample::select(simple_tokens()).prop_map(|(token, src)| (token, src.to_string())),
+                "[a-z_][a-z0-9_]{0,6}"
+                    .prop_filter("keyword", |name| keyword_to_token(name).is_none())
+                    .prop_map(|name| (Token::Name(name.clone()), name)),
+                (0..1_000_000i64).prop_map(|i| (Token::IntLiteral(i), i.to_string())),
+                (0..1000i64, 1..10i64).prop_map(|(i, f)| {
+                    let src = format!("{}.{}", i, f);
+                    (Token::FloatLiteral(src.parse().unwrap()), src)
+                }),
+                "[a-z =+!]{0,8}".prop_map(|s| (Token::StringLiteral(s.clone()), format!("\"{}\"", s))),
+            ]
+        }
+
+        /// whether two tokens would be read as one token or as different tokens without whitespace between them
+        fn needs_space(prev: &(Token, String), next: &str) -> bool {
+            let prev_end = prev.1.chars().last().unwrap();
+            let next_start = next.chars().next().unwrap();
+            let word_boundary = is_name_char(prev_end) && is_name_char(next_start);
+            let operator_boundary = (matches!(prev.0, Token::Assignment | Token::More | Token::Less | Token::Plus
+                    | Token::Minus | Token::Multi | Token::Devide | Token::Percent) && next_start == '=')
+                || (prev.0 == Token::Multi && next_start == '*')
+                || (prev.0 == Token::Devide && next_start == '/')
+                || (prev.0 == Token::Assignment && next_start == '>');
+            let number_dot = matches!(prev.0, Token::IntLiteral(_) | Token::FloatLiteral(_)) && next_start == '.';
+            word_boundary || operator_boundary || number_dot
+        }
+
+        proptest! {
+            #[test]
+            fn whitespace_does_not_change_tokens(tokens in proptest::collection::vec(token(), 1..40)) {
+                let mut compact = String::new();
+                let mut spaced = String::new();
+                for (i, token) in tokens.iter().enumerate() {
+                    if i > 0 && needs_space(&tokens[i - 1], &token.1) {
+                        compact.push(' ');
+                    }
+                    compact.push_str(&token.1);
+                    spaced.push_str(" \t");
+                    spaced.push_str(&token.1);
+                }
+                let mut expected: Vec<Token> = tokens.iter().map(|(token, _)| token.clone()).collect();
+                if expected.last() != Some(&Token::NewLine) {
+                    expected.push(Token::NewLine);
+                }
+                prop_assert_eq!(tokenize(&compact).unwrap(), expected.clone(), "program: {:?}", compact);
+                prop_assert_eq!(tokenize(&spaced).unwrap(), expected, "program: {:?}", spaced);
+            }
+        }
+    }
+}
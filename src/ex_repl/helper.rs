@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use rustyline::completion::{Completer, Pair};
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::{Context, Helper, Highlighter, Hinter};
+
+use crate::ex_core::Interpreter;
+use super::session::COMMANDS;
+
+/// Line editor helper of the command line interpreter:
+/// completes names of variables, functions and methods,
+/// and continues input on the next line while brackets are not balanced
+#[derive(Helper, Highlighter, Hinter, Default)]
+pub struct ReplHelper {
+    var_types: HashMap<String, &'static str>,
+    func_names: Vec<String>,
+    method_names: HashMap<&'static str, Vec<String>>,
+    all_method_names: Vec<String>,
+}
+
+impl ReplHelper {
+    /// updates completion candidates from the interpreter state
+    pub fn update_names(&mut self, interp: &Interpreter) {
+        self.var_types = interp.get_vars().into_iter().map(|(name, value)| (name, value.type_name())).collect();
+        self.func_names = interp.get_func_names();
+        self.method_names.clear();
+        for type_name in self.var_types.values() {
+            self.method_names.entry(type_name).or_insert_with(|| interp.get_method_names(Some(type_name)));
+        }
+        self.all_method_names = interp.get_method_names(None);
+    }
+
+    fn candidates(&self, line: &str, word_start: usize) -> Vec<String> {
+        let before_word = &line[..word_start];
+        if before_word == ":" {
+            return COMMANDS.iter()
+                .map(|(usage, _)| usage.split(' ').next().unwrap_or(usage)[1..].to_string())
+                .collect();
+        }
+        if let Some(receiver) = before_word.strip_suffix('.') {
+            let receiver_start = receiver.rfind(|ch: char| !is_name_char(ch)).map(|i| i + 1).unwrap_or(0);
+            let receiver = &receiver[receiver_start..];
+            return match self.var_types.get(receiver) {
+                Some(type_name) => self.method_names.get(type_name).cloned().unwrap_or_default(),
+                None => self.all_method_names.clone()
+            };
+        }
+        let mut names : Vec<String> = self.var_types.keys().cloned().chain(self.func_names.iter().cloned()).collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+fn is_name_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+/// returns true if all brackets of 'input' are closed,
+/// brackets inside string literals and comments are ignored
+pub fn is_input_complete(input: &str) -> bool {
+    let mut depth : i64 = 0;
+    let mut in_string = false;
+    let mut in_comment = false;
+    let mut chars = input.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\n' => {
+                in_string = false;
+                in_comment = false;
+            }
+            _ if in_comment => {}
+            '"' => in_string = !in_string,
+            '/' if !in_string && chars.peek() == Some(&'/') => in_comment = true,
+            '(' | '{' | '[' if !in_string => depth += 1,
+            ')' | '}' | ']' if !in_string => depth -= 1,
+            _ => {}
+        }
+    }
+    depth <= 0
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let line = &line[..pos];
+        let word_start = line.rfind(|ch: char| !is_name_char(ch)).map(|i| i + 1).unwrap_or(0);
+        let word = &line[word_start..];
+        let candidates = self.candidates(line, word_start)
+            .into_iter()
+            .filter(|name| name.starts_with(word))
+            .map(|name| Pair { display: name.clone(), replacement: name })
+            .collect();
+        Ok((word_start, candidates))
+    }
+}
+
+impl Validator for ReplHelper {
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        if is_input_complete(ctx.input()) {
+            Ok(ValidationResult::Valid(None))
+        } else {
+            Ok(ValidationResult::Incomplete)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn input_complete_test() {
+        assert!(is_input_complete("a = 1"));
+        assert!(is_input_complete("fn f(a) {\n return a \n}"));
+        assert!(is_input_complete("s = \"{\""));
+        assert!(is_input_complete("}"));
+        assert!(!is_input_complete("fn f(a) {"));
+        assert!(!is_input_complete("if a {\n while b {\n }"));
+        assert!(!is_input_complete("l = [1,"));
+        assert!(is_input_complete("x = 1 // {"));
+        assert!(is_input_complete("s = \"//\" + \"}\""));
+        assert!(!is_input_complete("if a { // }\n b = 1"));
+        assert!(!is_input_complete("x = 1 // (\n f("));
+    }
+
+    #[test]
+    fn completion_candidates_test() {
+        let mut interp = Interpreter::new();
+        interp.add_var("counter", &crate::ex_core::ValueVariant::Integer(1));
+        let mut helper = ReplHelper::default();
+        helper.update_names(&interp);
+        assert!(helper.candidates("cou", 0).contains(&"counter".to_string()));
+        assert!(helper.candidates("wri", 0).contains(&"writeln".to_string()));
+        let methods = helper.candidates("counter.p", 8);
+        assert_eq!(methods, vec!["pow".to_string(), "to_char".to_string()]);
+        assert!(helper.candidates(":va", 1).contains(&"vars".to_string()));
+    }
+}
@@ -0,0 +1,76 @@
+mod helper;
+mod session;
+
+use std::env;
+use std::path::PathBuf;
+
+use rustyline::error::ReadlineError;
+use rustyline::history::DefaultHistory;
+use rustyline::Editor;
+
+use helper::{is_input_complete, ReplHelper};
+use session::{InputResult, Session};
+
+const PROMPT: &str = ">>> ";
+const CONTINUATION_PROMPT: &str = "... ";
+const HISTORY_FILE: &str = ".ex_history";
+
+fn history_path() -> Option<PathBuf> {
+    env::var("HOME").or_else(|_| env::var("USERPROFILE")).ok().map(|home| PathBuf::from(home).join(HISTORY_FILE))
+}
+
+/// runs the command line interpreter until EOF (Ctrl-D)
+pub fn run_repl() -> rustyline::Result<()> {
+    println!("Run commnand line Ex interpreter, type :help for commands, press Ctrl-D to exit");
+    let mut session = Session::new();
+    let mut editor : Editor<ReplHelper, DefaultHistory> = Editor::new()?;
+    let mut helper = ReplHelper::default();
+    helper.update_names(&session.interp);
+    editor.set_helper(Some(helper));
+    let history = history_path();
+    if let Some(path) = history.as_ref() {
+        // there is no history on the first run
+        let _ = editor.load_history(path);
+    }
+    // lines of not yet complete input, when stdin is not a terminal
+    // the editor returns input line by line without validation
+    let mut pending_input = String::new();
+    loop {
+        let prompt = if pending_input.is_empty() { PROMPT } else { CONTINUATION_PROMPT };
+        match editor.readline(prompt) {
+            Ok(line) => {
+                pending_input.push_str(&line);
+                pending_input.push('\n');
+                if !is_input_complete(&pending_input) {
+                    continue;
+                }
+                let input = std::mem::take(&mut pending_input);
+                if input.trim().is_empty() {
+                    continue;
+                }
+                editor.add_history_entry(input.trim_end())?;
+                if let Some(path) = history.as_ref() {
+                    // history is saved after every input, because script can call exit()
+                    let _ = editor.save_history(path);
+                }
+                if let InputResult::Quit = session.run_input(&input) {
+                    break;
+                }
+                if let Some(helper) = editor.helper_mut() {
+                    helper.update_names(&session.interp);
+                }
+            }
+            Err(ReadlineError::Interrupted) => {
+                pending_input.clear();
+                println!("Input interrupted, press Ctrl-D to exit");
+            }
+            Err(ReadlineError::Eof) => {
+                break;
+            }
+            Err(err) => {
+                return Err(err);
+            }
+        }
+    }
+    Ok(())
+}
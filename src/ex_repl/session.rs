@@ -0,0 +1,240 @@
+use std::fs;
+use std::time::Instant;
+
+use crate::ex_core::{is_assignment_statement, tokenize, Expression, Interpreter, Parser, Token, ValueVariant};
+use super::helper::is_input_complete;
+
+/// meta commands of the command line interpreter with descriptions
+pub const COMMANDS: [(&str, &str); 9] = [
+    (":help", "show this message"),
+    (":vars", "list variables"),
+    (":funcs", "list user and std functions"),
+    (":type <expr>", "show the type of the expression value"),
+    (":time <code>", "evaluate the code and show evaluation time"),
+    (":load <file>", "interpret the file in the current session"),
+    (":save <file>", "save function definitions, assignments and changes of variables by methods to the file"),
+    (":reset", "reset the interpreter"),
+    (":quit", "exit the command line interpreter"),
+];
+
+/// result of the command line input
+pub enum InputResult {
+    Continue,
+    Quit,
+}
+
+/// State of the command line interpreter: the interpreter and the source code
+/// of definitions, that were successfully evaluated in the session
+pub struct Session {
+    pub interp: Interpreter,
+    definitions: Vec<String>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Session {
+            interp: Interpreter::new(),
+            definitions: Vec::new(),
+        }
+    }
+
+    /// evaluates the line of code or the meta command and prints the result
+    pub fn run_input(&mut self, input: &str) -> InputResult {
+        let trimmed = input.trim();
+        if trimmed.starts_with(':') {
+            let (command, arg) = trimmed.split_once(char::is_whitespace).unwrap_or((trimmed, ""));
+            return self.run_command(command, arg.trim());
+        }
+        match self.eval(input) {
+            // nil results, like results of writeln(), are not printed
+            Ok(Some(ValueVariant::Nil)) | Ok(None) => {}
+            Ok(Some(value)) => print_value(&value),
+            Err(err_msg) => println!("{}", err_msg),
+        }
+        InputResult::Continue
+    }
+
+    /// evaluates the code and stores its definitions, returns the value of the last expression
+    fn eval(&mut self, code: &str) -> Result<Option<ValueVariant>, String> {
+        let expr = parse(code)?;
+        for warning in self.interp.check(expr.as_ref()) {
+            println!("Warning: {}", warning);
+        }
+        let value = self.interp.interp_expr_with_result(expr).map_err(|err_msg| format!("Interpreter error: {}", err_msg))?;
+        self.definitions.extend(definition_chunks(code, &self.interp.get_mut_method_names()));
+        Ok(value)
+    }
+
+    /// evaluates the code without changing variables and functions of the session
+    fn eval_isolated(&mut self, code: &str) -> Result<Option<ValueVariant>, String> {
+        let expr = parse(code)?;
+        self.interp.interp_expr_isolated(expr).map_err(|err_msg| format!("Interpreter error: {}", err_msg))
+    }
+
+    fn run_command(&mut self, command: &str, arg: &str) -> InputResult {
+        let needs_arg = COMMANDS.iter().any(|(usage, _)| usage.starts_with(&format!("{} ", command)));
+        if needs_arg && arg.is_empty() {
+            println!("Command {} expects an argument, see :help", command);
+            return InputResult::Continue;
+        }
+        match command {
+            ":help" => {
+                for (usage, description) in COMMANDS.iter() {
+                    println!("{:<14} {}", usage, description);
+                }
+            }
+            ":vars" => {
+                for (name, value) in self.interp.get_vars() {
+                    print!("{}: {} = ", name, value.type_name());
+                    print_value(&value);
+                }
+            }
+            ":funcs" => {
+                println!("user functions:");
+                for signature in self.interp.get_user_func_signatures() {
+                    println!("    {}", signature);
+                }
+                println!("std functions:");
+                for signature in self.interp.get_std_func_signatures() {
+                    println!("    {}", signature);
+                }
+            }
+            ":type" => match self.eval_isolated(arg) {
+                Ok(Some(value)) => println!("{}", value.type_name()),
+                Ok(None) => println!("no value"),
+                Err(err_msg) => println!("{}", err_msg),
+            },
+            ":time" => {
+                let start = Instant::now();
+                let result = self.eval(arg);
+                let elapsed = start.elapsed();
+                match result {
+                    Ok(Some(value)) => print_value(&value),
+                    Ok(None) => {}
+                    Err(err_msg) => println!("{}", err_msg),
+                }
+                println!("time: {:?}", elapsed);
+            }
+            ":load" => match fs::read_to_string(arg) {
+                Ok(code) => {
+                    if let Err(err_msg) = self.eval(&code) {
+                        println!("{}", err_msg);
+                    }
+                }
+                Err(err) => println!("Can't read file {}: {}", arg, err),
+            },
+            ":save" => {
+                let mut code = self.definitions.join("\n");
+                code.push('\n');
+                match fs::write(arg, code) {
+                    Ok(()) => println!("Saved {} definitions to {}", self.definitions.len(), arg),
+                    Err(err) => println!("Can't write file {}: {}", arg, err),
+                }
+            }
+            ":reset" => {
+                *self = Session::new();
+            }
+            ":quit" => {
+                return InputResult::Quit;
+            }
+            _ => println!("Unknown command {}, see :help", command),
+        }
+        InputResult::Continue
+    }
+}
+
+pub fn print_value(value: &ValueVariant) {
+    match value {
+        ValueVariant::String(s) => println!("{:?}", s),
+        ValueVariant::Char(c) => println!("{:?}", c),
+        _ => println!("{}", value),
+    }
+}
+
+fn parse(code: &str) -> Result<Box<dyn Expression>, String> {
+    let tokens = tokenize(code).map_err(|err| format!("Lexer error: {}", err))?;
+    let mut parser = Parser::new(&tokens);
+    parser.parse().map_err(|err_msg| format!("Parse error: {}", err_msg))
+}
+
+/// splits code into top level statements and returns function definitions, imports, assignments
+/// and calls of methods, that change variables, like "xs.push(1)"
+fn definition_chunks(code: &str, mut_methods: &[String]) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut chunk = String::new();
+    for line in code.lines() {
+        chunk.push_str(line);
+        chunk.push('\n');
+        if !is_input_complete(&chunk) {
+            continue;
+        }
+        let statement = std::mem::take(&mut chunk);
+        let statement = statement.trim();
+        if is_definition(statement, mut_methods) {
+            chunks.push(statement.to_string());
+        }
+    }
+    chunks
+}
+
+fn is_definition(statement: &str, mut_methods: &[String]) -> bool {
+    if statement.starts_with("fn ") || statement.starts_with("import ") {
+        return true;
+    }
+    tokenize(statement).is_ok_and(|tokens| is_assignment_statement(&tokens) || match tokens.as_slice() {
+        [Token::Name(_), Token::Dot, Token::Name(method), Token::OpenBracket, ..] => mut_methods.contains(method),
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn definition_chunks_test() {
+        let code = "a = 1\nwriteln(a)\nfn f(x) {\n    writeln(x)\n}\nb == 2\nf(a)\nc=[1,\n2]\nc[0] += 1\nx, y = 1, 2\nimport utils as u\n\
+                    c.push(3)\nc.len()\nm.remove(\"k\")\nc.pop()\ns.push_str(\"x\")\nwriteln(c.pop())\n";
+        let mut_methods = Session::new().interp.get_mut_method_names();
+        assert_eq!(definition_chunks(code, &mut_methods), vec!["a = 1", "fn f(x) {\n    writeln(x)\n}", "c=[1,\n2]", "c[0] += 1", "x, y = 1, 2", "import utils as u",
+                                                              "c.push(3)", "m.remove(\"k\")", "c.pop()", "s.push_str(\"x\")"]);
+    }
+
+    #[test]
+    fn session_test() {
+        let mut session = Session::new();
+        session.run_input("a = 2");
+        session.run_input("fn sq(x) {\n return x * x\n}");
+        session.run_input("b = sq(a)");
+        session.run_input("xs = [b]");
+        session.run_input("xs.push(5)");
+        assert_eq!(session.eval("b").unwrap(), Some(ValueVariant::Integer(4)));
+        assert_eq!(session.interp.get_user_func_signatures(), vec!["sq(x)"]);
+
+        let path = std::env::temp_dir().join(format!("ex_session_test_{}.ex", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        session.run_input(&format!(":save {}", path));
+        session.run_input(":reset");
+        assert!(session.eval("b").is_err());
+        session.run_input(&format!(":load {}", path));
+        assert_eq!(session.eval("b").unwrap(), Some(ValueVariant::Integer(4)));
+        assert_eq!(session.eval("xs").unwrap().unwrap().to_string(), "[4, 5]");
+        fs::remove_file(path).unwrap();
+        assert!(matches!(session.run_input(":quit"), InputResult::Quit));
+    }
+
+    #[test]
+    fn type_command_test() {
+        let mut session = Session::new();
+        session.run_input("xs = [1]");
+        assert_eq!(session.eval_isolated("xs.len()").unwrap(), Some(ValueVariant::Integer(1)));
+        // the code of :type leaves no trace in the session
+        session.run_input(":type y = 2");
+        session.run_input(":type xs.push(2)");
+        session.run_input(":type fn g() {\n return 1\n}");
+        assert!(session.eval("y").is_err());
+        assert_eq!(session.eval("xs").unwrap().unwrap().to_string(), "[1]");
+        assert!(session.interp.get_user_func_signatures().is_empty());
+        assert_eq!(session.definitions, vec!["xs = [1]"]);
+    }
+}
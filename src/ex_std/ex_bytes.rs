@@ -0,0 +1,144 @@
+use crate::ex_core::ValueVariant;
+
+use super::method_repository::{StdMethodsMap, StdMethodsRepository, StdMethodArgs, StdMethodResult};
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// bytes of the text in the text encoding: "utf8" or "latin1"
+pub fn encode_text(text: &str, encoding: &str) -> Result<Vec<u8>, String> {
+    match encoding {
+        "utf8" | "utf-8" => Ok(text.as_bytes().to_vec()),
+        "latin1" => text.chars()
+            .map(|c| u8::try_from(c).map_err(|_| format!("char {:?} can't be encoded in latin1", c)))
+            .collect(),
+        _ => Err(format!("unknown text encoding '{}', expected utf8 or latin1", encoding))
+    }
+}
+
+/// text of the bytes in the text encoding: "utf8" or "latin1"
+pub fn decode_text(bytes: &[u8], encoding: &str) -> Result<String, String> {
+    match encoding {
+        "utf8" | "utf-8" => String::from_utf8(bytes.to_vec())
+            .map_err(|err| format!("bytes are not valid utf8: invalid byte at {}", err.utf8_error().valid_up_to())),
+        "latin1" => Ok(bytes.iter().map(|b| char::from(*b)).collect()),
+        _ => Err(format!("unknown text encoding '{}', expected utf8 or latin1", encoding))
+    }
+}
+
+/// text representation of the bytes: "base64" or "hex"
+pub fn encode_binary(bytes: &[u8], encoding: &str) -> Result<String, String> {
+    match encoding {
+        "base64" => {
+            let mut text = String::new();
+            for chunk in bytes.chunks(3) {
+                let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+                for i in 0..4 {
+                    if i <= chunk.len() {
+                        text.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+                    } else {
+                        text.push('=');
+                    }
+                }
+            }
+            Ok(text)
+        }
+        "hex" => Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect()),
+        _ => Err(format!("unknown binary encoding '{}', expected base64 or hex", encoding))
+    }
+}
+
+/// bytes of the text representation: "base64" or "hex"
+pub fn decode_binary(text: &str, encoding: &str) -> Result<Vec<u8>, String> {
+    match encoding {
+        "base64" => {
+            let digits = text.trim_end_matches('=').bytes()
+                .map(|c| BASE64_ALPHABET.iter().position(|a| *a == c).map(|d| d as u32)
+                    .ok_or(format!("not valid base64 char {:?}", c as char)))
+                .collect::<Result<Vec<u32>, String>>()?;
+            if digits.len() % 4 == 1 {
+                return Err(String::from("not valid base64 length"));
+            }
+            let mut bytes = Vec::new();
+            for chunk in digits.chunks(4) {
+                let n = chunk.iter().enumerate().fold(0u32, |n, (i, d)| n | d << (18 - 6 * i));
+                for i in 0..chunk.len() - 1 {
+                    bytes.push((n >> (16 - 8 * i)) as u8);
+                }
+            }
+            Ok(bytes)
+        }
+        "hex" => {
+            if !text.len().is_multiple_of(2) || !text.is_ascii() {
+                return Err(format!("not valid hex string \"{}\"", text));
+            }
+            (0..text.len()).step_by(2)
+                .map(|i| u8::from_str_radix(&text[i..i + 2], 16).map_err(|_| format!("not valid hex string \"{}\"", text)))
+                .collect()
+        }
+        _ => Err(format!("unknown binary encoding '{}', expected base64 or hex", encoding))
+    }
+}
+
+/// the only string argument of the method
+pub fn get_encoding(args: &StdMethodArgs) -> Result<&str, String> {
+    match args.as_slice() {
+        [ValueVariant::String(s)] => Ok(s),
+        [v] => Err(format!("exptected encoding name, find {}", v)),
+        _ => Err(format!("method arg expected 1 argument, find {}", args.len()))
+    }
+}
+
+pub struct BytesMethods {
+    methods: StdMethodsMap
+}
+
+impl BytesMethods {
+    pub fn new() -> Self {
+        let mut methods = StdMethodsMap::new();
+        methods.insert("len".to_string(), BytesMethods::len);
+        methods.insert("decode".to_string(), BytesMethods::decode);
+        methods.insert("encode".to_string(), BytesMethods::encode);
+        Self {
+            methods
+        }
+    }
+
+    fn get_bytes(v: &ValueVariant) -> Result<&[u8], String> {
+        match v {
+            ValueVariant::Bytes(b) => Ok(b),
+            _ => Err(format!("exptected bytes, find {}", v))
+        }
+    }
+
+    fn len(this: &ValueVariant, args: &StdMethodArgs) -> StdMethodResult {
+        if !args.is_empty() {
+            return Err(String::from("method arg expected 0 arguments"));
+        }
+        let this_b = Self::get_bytes(this)?;
+        Ok(Some(ValueVariant::Integer(this_b.len() as i64)))
+    }
+
+    /// decode("utf8") or decode("latin1") returns the text of the bytes
+    fn decode(this: &ValueVariant, args: &StdMethodArgs) -> StdMethodResult {
+        let encoding = get_encoding(args)?;
+        let text = decode_text(Self::get_bytes(this)?, encoding)?;
+        Ok(Some(ValueVariant::String(text.into())))
+    }
+
+    /// encode("base64") or encode("hex") returns the bytes as a string
+    fn encode(this: &ValueVariant, args: &StdMethodArgs) -> StdMethodResult {
+        let encoding = get_encoding(args)?;
+        let text = encode_binary(Self::get_bytes(this)?, encoding)?;
+        Ok(Some(ValueVariant::String(text.into())))
+    }
+}
+
+impl StdMethodsRepository for BytesMethods {
+    fn get_type_name(&self) -> &'static str {
+        "bytes"
+    }
+
+    fn get_methods(&self) -> StdMethodsMap {
+        self.methods.clone()
+    }
+}
@@ -0,0 +1,62 @@
+use crate::ex_core::ValueVariant;
+
+use super::method_repository::{StdMethodsMap, StdMethodsRepository, StdMethodArgs, StdMethodResult};
+
+
+pub struct CharMethods {
+    methods: StdMethodsMap
+}
+
+impl CharMethods {
+    pub fn new() -> Self {
+        let mut methods = StdMethodsMap::new();
+        methods.insert("code".to_string(), CharMethods::code);
+        methods.insert("is_digit".to_string(), CharMethods::is_digit);
+        methods.insert("is_alpha".to_string(), CharMethods::is_alpha);
+        methods.insert("is_whitespace".to_string(), CharMethods::is_whitespace);
+        Self {
+            methods
+        }
+    }
+
+    fn get_char(v: &ValueVariant, args: &StdMethodArgs) -> Result<char, String> {
+        if !args.is_empty() {
+            return Err(String::from("method arg expected 0 arguments"));
+        }
+        match v {
+            ValueVariant::Char(c) => Ok(*c),
+            _ => Err(format!("exptected char, find {}", v))
+        }
+    }
+
+    /// unicode code point of the char
+    fn code(this: &ValueVariant, args: &StdMethodArgs) -> StdMethodResult {
+        let c = Self::get_char(this, args)?;
+        Ok(Some(ValueVariant::Integer(c as i64)))
+    }
+
+    fn is_digit(this: &ValueVariant, args: &StdMethodArgs) -> StdMethodResult {
+        let c = Self::get_char(this, args)?;
+        Ok(Some(ValueVariant::Bool(c.is_ascii_digit())))
+    }
+
+    fn is_alpha(this: &ValueVariant, args: &StdMethodArgs) -> StdMethodResult {
+        let c = Self::get_char(this, args)?;
+        Ok(Some(ValueVariant::Bool(c.is_alphabetic())))
+    }
+
+    fn is_whitespace(this: &ValueVariant, args: &StdMethodArgs) -> StdMethodResult {
+        let c = Self::get_char(this, args)?;
+        Ok(Some(ValueVariant::Bool(c.is_whitespace())))
+    }
+}
+
+impl StdMethodsRepository for CharMethods {
+    fn get_type_name(&self) -> &'static str {
+        "char"
+    }
+
+    fn get_methods(&self) -> StdMethodsMap {
+        self.methods.clone()
+    }
+}
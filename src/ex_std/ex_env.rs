@@ -0,0 +1,95 @@
+use std::{collections::HashMap, env, io::{self, Write}, process};
+use crate::ex_core::ValueVariant;
+
+use super::func_respository::{FunctionRepository, StdFuncResult, StdFuncArgs, StdFuncMap, StdFunc, StdFuncSignatures};
+
+pub struct EnvFunctionRepo {
+    funcs : StdFuncMap,
+    signatures : StdFuncSignatures,
+}
+impl EnvFunctionRepo {
+    pub fn new() -> Self {
+        let mut funcs : HashMap<String, StdFunc> = HashMap::new();
+        funcs.insert("env".to_string(), EnvFunctionRepo::env);
+        funcs.insert("set_env".to_string(), EnvFunctionRepo::set_env);
+        funcs.insert("unset_env".to_string(), EnvFunctionRepo::unset_env);
+        funcs.insert("exit".to_string(), EnvFunctionRepo::exit);
+        let mut signatures = StdFuncSignatures::new();
+        signatures.insert("env".to_string(), "env(name, [default])".to_string());
+        signatures.insert("set_env".to_string(), "set_env(name, value)".to_string());
+        signatures.insert("unset_env".to_string(), "unset_env(name)".to_string());
+        signatures.insert("exit".to_string(), "exit([code])".to_string());
+        Self {
+            funcs,
+            signatures
+        }
+    }
+
+    fn get_str(v: &ValueVariant) -> Result<String, String> {
+        match v {
+            ValueVariant::String(s) => Ok(s.to_string()),
+            _ => Err(format!("expected string, find {}", v))
+        }
+    }
+
+    /// env(name) or env(name, default)
+    /// returns value of the environment variable, or default if variable is not set
+    fn env(args: &StdFuncArgs) -> StdFuncResult {
+        if args.is_empty() || args.len() > 2 {
+            return Err(format!("expected 1 or 2 arguments, find {}", args.len()));
+        }
+        let name = Self::get_str(&args[0])?;
+        match env::var(&name) {
+            Ok(value) => Ok(Some(ValueVariant::String(value.into()))),
+            Err(_) if args.len() == 2 => Ok(Some(args[1].clone())),
+            Err(err) => Err(format!("can't read environment variable {}: {}", name, err))
+        }
+    }
+
+    fn set_env(args: &StdFuncArgs) -> StdFuncResult {
+        if args.len() != 2 {
+            return Err(format!("expected 2 arguments, find {}", args.len()));
+        }
+        let name = Self::get_str(&args[0])?;
+        if name.is_empty() || name.contains('=') || name.contains('\0') {
+            return Err(format!("not valid environment variable name '{}'", name));
+        }
+        env::set_var(name, args[1].to_string());
+        Ok(None)
+    }
+
+    fn unset_env(args: &StdFuncArgs) -> StdFuncResult {
+        if args.len() != 1 {
+            return Err(format!("expected 1 argument, find {}", args.len()));
+        }
+        let name = Self::get_str(&args[0])?;
+        if name.is_empty() || name.contains('=') || name.contains('\0') {
+            return Err(format!("not valid environment variable name '{}'", name));
+        }
+        env::remove_var(name);
+        Ok(None)
+    }
+
+    /// exit() or exit(code)
+    /// terminates the process with the given exit status, 0 by default
+    fn exit(args: &StdFuncArgs) -> StdFuncResult {
+        let code = match args.as_slice() {
+            [] => 0,
+            [ValueVariant::Integer(i)] => i32::try_from(*i).map_err(|_| format!("exit code {} out of range", i))?,
+            [v] => return Err(format!("expected int exit code, find {}", v)),
+            _ => return Err(format!("expected 0 or 1 argument, find {}", args.len()))
+        };
+        io::stdout().flush().expect("Failed to flush stdout");
+        process::exit(code);
+    }
+}
+
+impl FunctionRepository for EnvFunctionRepo {
+    fn get_functions(&self) -> StdFuncMap {
+        self.funcs.clone()
+    }
+
+    fn get_signatures(&self) -> StdFuncSignatures {
+        self.signatures.clone()
+    }
+}
@@ -0,0 +1,392 @@
+use std::{collections::HashMap, env, fs, path::Path, rc::Rc};
+use crate::ex_core::ValueVariant;
+
+use super::func_respository::{FunctionRepository, StdFuncResult, StdFuncArgs, StdFuncMap, StdFunc, StdFuncSignatures};
+
+/// Character of a word with a flag, that is set if the character is quoted
+/// (escaped with '\' or produced by an expansion) and must be taken literally
+type WordChar = (char, bool);
+type Word = Vec<WordChar>;
+
+/// characters that have special meaning in word expansion
+const SPECIAL_CHARS: &str = "\\*?[]{},~$";
+
+/// maximum number of words, that brace expansion of one word produces
+const MAX_BRACE_WORDS: usize = 100000;
+
+/// Expands 'word' the way Bash expands an unquoted word, but without running any shell:
+/// brace expansion ({a,b}, {1..3}), tilde expansion (~/), environment variables ($VAR, ${VAR})
+/// and pathname expansion (*, ?, [...]).
+/// Characters escaped with '\' are taken literally. Values of expanded variables and home dir
+/// are never expanded again, so they can't inject patterns.
+/// If a pattern matches no files, it is kept as is (with escapes removed).
+/// Fails, if braces expand to more than MAX_BRACE_WORDS words.
+pub fn expand_word(word: &str) -> Result<Vec<String>, String> {
+    let mut result = Vec::new();
+    for word in expand_braces(&parse_word(word))? {
+        let word = expand_variables(&expand_tilde(word));
+        if has_glob_chars(&word) {
+            let matches = glob(&word);
+            if !matches.is_empty() {
+                result.extend(matches);
+                continue;
+            }
+        }
+        result.push(word.iter().map(|(ch, _)| ch).collect());
+    }
+    Ok(result)
+}
+
+/// Escapes all special characters of 's', so expand_word returns 's' unchanged
+pub fn quote_word(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for ch in s.chars() {
+        if SPECIAL_CHARS.contains(ch) {
+            result.push('\\');
+        }
+        result.push(ch);
+    }
+    result
+}
+
+fn parse_word(word: &str) -> Word {
+    let mut result = Word::new();
+    let mut chars = word.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\\' {
+            if let Some(escaped) = chars.next() {
+                result.push((escaped, true));
+            } else {
+                result.push((ch, true));
+            }
+        } else {
+            result.push((ch, false));
+        }
+    }
+    result
+}
+
+fn is_special(ch: &WordChar, special: char) -> bool {
+    !ch.1 && ch.0 == special
+}
+
+fn expand_braces(word: &Word) -> Result<Vec<Word>, String> {
+    // find the first brace pair with a top level comma or a valid sequence
+    let mut open_stack: Vec<usize> = Vec::new();
+    for (i, ch) in word.iter().enumerate() {
+        if is_special(ch, '{') {
+            open_stack.push(i);
+        } else if is_special(ch, '}') {
+            if let Some(open) = open_stack.pop() {
+                if !open_stack.is_empty() {
+                    continue;
+                }
+                if let Some(alternatives) = brace_alternatives(&word[open + 1..i])? {
+                    let mut result = Vec::new();
+                    for alternative in alternatives {
+                        let mut expanded = word[..open].to_vec();
+                        expanded.extend(alternative);
+                        expanded.extend_from_slice(&word[i + 1..]);
+                        result.extend(expand_braces(&expanded)?);
+                        if result.len() > MAX_BRACE_WORDS {
+                            return Err(format!("braces expand to more than {} words", MAX_BRACE_WORDS));
+                        }
+                    }
+                    return Ok(result);
+                }
+            }
+        }
+    }
+    Ok(vec![word.clone()])
+}
+
+/// returns alternatives of brace content or None if braces must be kept literally
+fn brace_alternatives(content: &[WordChar]) -> Result<Option<Vec<Word>>, String> {
+    let mut alternatives = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, ch) in content.iter().enumerate() {
+        if is_special(ch, '{') {
+            depth += 1;
+        } else if is_special(ch, '}') {
+            depth -= 1;
+        } else if depth == 0 && is_special(ch, ',') {
+            alternatives.push(content[start..i].to_vec());
+            start = i + 1;
+        }
+    }
+    if !alternatives.is_empty() {
+        alternatives.push(content[start..].to_vec());
+        return Ok(Some(alternatives));
+    }
+    brace_sequence(content)
+}
+
+/// {start..end} sequence of integers, it fails, if it's longer than MAX_BRACE_WORDS
+fn brace_sequence(content: &[WordChar]) -> Result<Option<Vec<Word>>, String> {
+    let text: String = content.iter().map(|(ch, _)| ch).collect();
+    let Some((start, end)) = text.split_once("..") else {
+        return Ok(None);
+    };
+    let (Ok(start), Ok(end)) = (start.parse::<i64>(), end.parse::<i64>()) else {
+        return Ok(None);
+    };
+    if start.abs_diff(end) >= MAX_BRACE_WORDS as u64 {
+        return Err(format!("sequence {{{}}} is longer than {} words", text, MAX_BRACE_WORDS));
+    }
+    let numbers: Vec<i64> = if start <= end {
+        (start..=end).collect()
+    } else {
+        (end..=start).rev().collect()
+    };
+    Ok(Some(numbers.iter().map(|n| n.to_string().chars().map(|ch| (ch, true)).collect()).collect()))
+}
+
+fn home_dir() -> Option<String> {
+    env::var("HOME").or_else(|_| env::var("USERPROFILE")).ok()
+}
+
+fn expand_tilde(word: Word) -> Word {
+    if word.first().is_some_and(|ch| is_special(ch, '~')) && (word.len() == 1 || word[1].0 == '/') {
+        if let Some(home) = home_dir() {
+            let mut result: Word = home.chars().map(|ch| (ch, true)).collect();
+            result.extend_from_slice(&word[1..]);
+            return result;
+        }
+    }
+    word
+}
+
+fn is_var_char(ch: &WordChar) -> bool {
+    ch.0.is_ascii_alphanumeric() || ch.0 == '_'
+}
+
+fn expand_variables(word: &Word) -> Word {
+    let mut result = Word::new();
+    let mut i = 0;
+    while i < word.len() {
+        if is_special(&word[i], '$') {
+            let (name_start, name_end, next) = if word.get(i + 1).is_some_and(|ch| is_special(ch, '{')) {
+                match word[i + 2..].iter().position(|ch| is_special(ch, '}')) {
+                    Some(len) => (i + 2, i + 2 + len, i + 3 + len),
+                    None => (i + 1, i + 1, i + 1)
+                }
+            } else {
+                let len = word[i + 1..].iter().take_while(|ch| is_var_char(ch)).count();
+                (i + 1, i + 1 + len, i + 1 + len)
+            };
+            if name_end > name_start {
+                let name: String = word[name_start..name_end].iter().map(|(ch, _)| ch).collect();
+                let value = env::var(name).unwrap_or_default();
+                result.extend(value.chars().map(|ch| (ch, true)));
+                i = next;
+                continue;
+            }
+        }
+        result.push(word[i]);
+        i += 1;
+    }
+    result
+}
+
+fn has_glob_chars(word: &[WordChar]) -> bool {
+    word.iter().any(|ch| is_special(ch, '*') || is_special(ch, '?') || is_special(ch, '['))
+}
+
+fn glob(pattern: &Word) -> Vec<String> {
+    let is_absolute = pattern.first().is_some_and(|ch| ch.0 == '/');
+    let mut candidates = vec![if is_absolute { String::from("/") } else { String::new() }];
+    let segments: Vec<&[WordChar]> = pattern.split(|ch| ch.0 == '/').filter(|s| !s.is_empty()).collect();
+    for segment in segments.iter() {
+        let mut next_candidates = Vec::new();
+        for base in candidates.iter() {
+            if !has_glob_chars(segment) {
+                let name: String = segment.iter().map(|(ch, _)| ch).collect();
+                next_candidates.push(base.clone() + &name);
+                continue;
+            }
+            let dir = if base.is_empty() { Path::new(".") } else { Path::new(base) };
+            let Ok(entries) = fs::read_dir(dir) else {
+                continue;
+            };
+            let mut names: Vec<String> = entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter(|name| glob_match(segment, &name.chars().collect::<Vec<char>>()))
+                .collect();
+            names.sort();
+            next_candidates.extend(names.into_iter().map(|name| base.clone() + &name));
+        }
+        candidates = next_candidates.into_iter().map(|c| c + "/").collect();
+    }
+    let keep_trailing_slash = pattern.last().is_some_and(|ch| ch.0 == '/');
+    candidates
+        .into_iter()
+        .map(|c| if keep_trailing_slash { c } else { c.trim_end_matches('/').to_string() })
+        .filter(|c| !c.is_empty() && fs::symlink_metadata(c).is_ok())
+        .collect()
+}
+
+/// matches file name against one path segment of a glob pattern,
+/// hidden files are matched only by a pattern starting with '.'
+fn glob_match(pattern: &[WordChar], name: &[char]) -> bool {
+    if name.first() == Some(&'.') && pattern.first().map(|ch| ch.0) != Some('.') {
+        return false;
+    }
+    match_from(pattern, name)
+}
+
+fn match_from(pattern: &[WordChar], name: &[char]) -> bool {
+    let Some((first, rest)) = pattern.split_first() else {
+        return name.is_empty();
+    };
+    if is_special(first, '*') {
+        return (0..=name.len()).any(|skip| match_from(rest, &name[skip..]));
+    }
+    let Some((name_first, name_rest)) = name.split_first() else {
+        return false;
+    };
+    if is_special(first, '?') {
+        return match_from(rest, name_rest);
+    }
+    if is_special(first, '[') {
+        if let Some((matched, class_len)) = match_class(rest, *name_first) {
+            return matched && match_from(&rest[class_len..], name_rest);
+        }
+    }
+    first.0 == *name_first && match_from(rest, name_rest)
+}
+
+/// matches [...] character class, 'class' starts after '[',
+/// returns match result and length of the class including closing ']'
+fn match_class(class: &[WordChar], ch: char) -> Option<(bool, usize)> {
+    let mut i = 0;
+    let negate = class.first().is_some_and(|c| is_special(c, '!') || is_special(c, '^'));
+    if negate {
+        i += 1;
+    }
+    let mut matched = false;
+    let mut first = true;
+    while i < class.len() {
+        let c = class[i];
+        if is_special(&c, ']') && !first {
+            return Some((matched != negate, i + 1));
+        }
+        first = false;
+        if class.get(i + 1).is_some_and(|d| is_special(d, '-')) && class.get(i + 2).is_some_and(|e| !is_special(e, ']')) {
+            if c.0 <= ch && ch <= class[i + 2].0 {
+                matched = true;
+            }
+            i += 3;
+        } else {
+            if c.0 == ch {
+                matched = true;
+            }
+            i += 1;
+        }
+    }
+    None
+}
+
+pub struct ExpandFunctionRepo {
+    funcs : StdFuncMap,
+    signatures : StdFuncSignatures,
+}
+impl ExpandFunctionRepo {
+    pub fn new() -> Self {
+        let mut funcs : HashMap<String, StdFunc> = HashMap::new();
+        funcs.insert("expand".to_string(), ExpandFunctionRepo::expand);
+        funcs.insert("quote".to_string(), ExpandFunctionRepo::quote);
+        let mut signatures = StdFuncSignatures::new();
+        signatures.insert("expand".to_string(), "expand(word)".to_string());
+        signatures.insert("quote".to_string(), "quote(value)".to_string());
+        Self {
+            funcs,
+            signatures
+        }
+    }
+
+    /// expand(word) returns list of words, the way 'word' is expanded in command args
+    fn expand(args: &StdFuncArgs) -> StdFuncResult {
+        match args.as_slice() {
+            [ValueVariant::String(s)] => {
+                Ok(Some(ValueVariant::List(Rc::new(expand_word(s)?.into_iter().map(|word| ValueVariant::String(word.into())).collect()))))
+            }
+            [v] => Err(format!("expected string, find {}", v)),
+            _ => Err(format!("expected 1 argument, find {}", args.len()))
+        }
+    }
+
+    /// quote(value) returns string, that is passed to commands as is, without expansion
+    fn quote(args: &StdFuncArgs) -> StdFuncResult {
+        if args.len() != 1 {
+            return Err(format!("expected 1 argument, find {}", args.len()));
+        }
+        Ok(Some(ValueVariant::String(quote_word(&args[0].to_string()).into())))
+    }
+}
+
+impl FunctionRepository for ExpandFunctionRepo {
+    fn get_functions(&self) -> StdFuncMap {
+        self.funcs.clone()
+    }
+
+    fn get_signatures(&self) -> StdFuncSignatures {
+        self.signatures.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brace_expansion_test() {
+        assert_eq!(expand_word("a{b,c}d").unwrap(), vec!["abd", "acd"]);
+        assert_eq!(expand_word("{x,y{1,2}}").unwrap(), vec!["x", "y1", "y2"]);
+        assert_eq!(expand_word("f{1..3}").unwrap(), vec!["f1", "f2", "f3"]);
+        assert_eq!(expand_word("{a}").unwrap(), vec!["{a}"]);
+        assert_eq!(expand_word("\\{a,b}").unwrap(), vec!["{a,b}"]);
+        assert_eq!(expand_word("{3..1}").unwrap(), vec!["3", "2", "1"]);
+        assert_eq!(expand_word("{0..9999999999}").unwrap_err(), "sequence {0..9999999999} is longer than 100000 words");
+        assert_eq!(expand_word("{1..1000}{1..1000}").unwrap_err(), "braces expand to more than 100000 words");
+        assert_eq!(expand_word("{1..100000}").unwrap().len(), 100000);
+    }
+
+    #[test]
+    fn variable_and_tilde_expansion_test() {
+        env::set_var("EX_EXPAND_TEST", "*{a,b}");
+        assert_eq!(expand_word("pre_$EX_EXPAND_TEST").unwrap(), vec!["pre_*{a,b}"]);
+        assert_eq!(expand_word("${EX_EXPAND_TEST}_post").unwrap(), vec!["*{a,b}_post"]);
+        assert_eq!(expand_word("\\$EX_EXPAND_TEST").unwrap(), vec!["$EX_EXPAND_TEST"]);
+        assert_eq!(expand_word("$EX_EXPAND_TEST_UNSET_VAR").unwrap(), vec![""]);
+        let home = home_dir().unwrap();
+        assert_eq!(expand_word("~/x").unwrap(), vec![home + "/x"]);
+        assert_eq!(expand_word("a~").unwrap(), vec!["a~"]);
+    }
+
+    #[test]
+    fn glob_test() {
+        let dir = env::temp_dir().join(format!("ex_glob_test_{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        for name in ["a.log", "b.log", "c.txt", ".hidden.log", "sub/d.log"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+        let base = quote_word(dir.to_str().unwrap());
+        let prefix = dir.to_str().unwrap().to_string();
+        assert_eq!(expand_word(&(base.clone() + "/*.log")).unwrap(), vec![prefix.clone() + "/a.log", prefix.clone() + "/b.log"]);
+        assert_eq!(expand_word(&(base.clone() + "/[ac].*")).unwrap(), vec![prefix.clone() + "/a.log", prefix.clone() + "/c.txt"]);
+        assert_eq!(expand_word(&(base.clone() + "/[!a]?log")).unwrap(), vec![prefix.clone() + "/b.log"]);
+        assert_eq!(expand_word(&(base.clone() + "/*/*.log")).unwrap(), vec![prefix.clone() + "/sub/d.log"]);
+        assert_eq!(expand_word(&(base.clone() + "/.*.log")).unwrap(), vec![prefix.clone() + "/.hidden.log"]);
+        assert_eq!(expand_word(&(base.clone() + "/*.none")).unwrap(), vec![prefix.clone() + "/*.none"]);
+        assert_eq!(expand_word(&(base.clone() + "/\\*.log")).unwrap(), vec![prefix.clone() + "/*.log"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn quote_test() {
+        let s = "~/$HOME/*.{a,b}[x]\\";
+        assert_eq!(expand_word(&quote_word(s)).unwrap(), vec![s]);
+    }
+}
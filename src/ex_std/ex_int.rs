@@ -11,6 +11,7 @@ impl IntMethods {
     pub fn new() -> Self {
         let mut methods = StdMethodsMap::new();
         methods.insert("pow".to_string(), IntMethods::pow);
+        methods.insert("to_char".to_string(), IntMethods::to_char);
         return Self {
             methods : methods
         }
@@ -36,11 +37,21 @@ impl IntMethods {
         return Ok(Some(ValueVariant::Integer(this_i.pow(pow as u32))));
     }
 
+    /// char with the unicode code point
+    fn to_char(this: &ValueVariant, args:&StdMethodArgs) -> StdMethodResult {
+        if !args.is_empty() {
+            return Err(String::from("method arg expected 0 arguments"));
+        }
+        let this_i = Self::to_int(this)?;
+        let c = u32::try_from(this_i).ok().and_then(char::from_u32).ok_or(format!("{} is not a valid char code", this_i))?;
+        Ok(Some(ValueVariant::Char(c)))
+    }
+
 }
 
 impl StdMethodsRepository for IntMethods {
-    fn get_diterminant(&self) -> std::mem::Discriminant<crate::ex_core::ValueVariant> {
-        return std::mem::discriminant(&crate::ex_core::ValueVariant::Integer(0));
+    fn get_type_name(&self) -> &'static str {
+        return "int";
     }
 
     fn get_methods(&self) -> StdMethodsMap {
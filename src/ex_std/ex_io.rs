@@ -1,10 +1,11 @@
-use std::{collections::HashMap, io::{self, Write}};
+use std::{collections::HashMap, fs, io::{self, Write}, rc::Rc};
 use crate::ex_core::ValueVariant;
 
-use super::func_respository::{FunctionRepository, StdFuncResult, StdFuncArgs, StdFuncMap, StdFunc};
+use super::func_respository::{FunctionRepository, StdFuncResult, StdFuncArgs, StdFuncMap, StdFunc, StdFuncSignatures};
 //use crate::core::interp::ValueVariant;
 pub struct IOFunctionRepo {
     funcs : StdFuncMap,
+    signatures : StdFuncSignatures,
 }
 impl IOFunctionRepo {
     pub fn new() -> Self {
@@ -12,8 +13,19 @@ impl IOFunctionRepo {
         funcs.insert("write".to_string(), IOFunctionRepo::write);
         funcs.insert("writeln".to_string(), IOFunctionRepo::writeln);
         funcs.insert("read".to_string(), IOFunctionRepo::read);
+        funcs.insert("read_file".to_string(), IOFunctionRepo::read_file);
+        funcs.insert("read_bytes".to_string(), IOFunctionRepo::read_bytes);
+        funcs.insert("write_file".to_string(), IOFunctionRepo::write_file);
+        let mut signatures = StdFuncSignatures::new();
+        signatures.insert("write".to_string(), "write(values...)".to_string());
+        signatures.insert("writeln".to_string(), "writeln(values...)".to_string());
+        signatures.insert("read".to_string(), "read()".to_string());
+        signatures.insert("read_file".to_string(), "read_file(path)".to_string());
+        signatures.insert("read_bytes".to_string(), "read_bytes(path)".to_string());
+        signatures.insert("write_file".to_string(), "write_file(path, value)".to_string());
         Self {
-            funcs : funcs
+            funcs : funcs,
+            signatures
         }
     }
     
@@ -37,11 +49,48 @@ impl IOFunctionRepo {
 
     fn read(_: &StdFuncArgs) -> StdFuncResult {
         let mut input = String::new();
-        if let Err(err) = io::stdin().read_line(&mut input) {
-            return Err(err.to_string());
+        match io::stdin().read_line(&mut input) {
+            // end of input
+            Ok(0) => return Ok(Some(ValueVariant::Nil)),
+            Ok(_) => {}
+            Err(err) => return Err(err.to_string()),
         }
         input = input.strip_suffix("\r\n").or(input.strip_suffix("\n")).unwrap_or(&input).to_owned();
-        return Ok(Some(ValueVariant::String(input.clone())));
+        return Ok(Some(ValueVariant::String(input.into())));
+    }
+
+    fn get_path(args: &StdFuncArgs, argc: usize) -> Result<&str, String> {
+        if args.len() != argc {
+            return Err(format!("expected {} arguments, find {}", argc, args.len()));
+        }
+        match &args[0] {
+            ValueVariant::String(path) => Ok(path),
+            v => Err(format!("expected path string, find {}", v))
+        }
+    }
+
+    /// contents of the text file, the file must be valid utf-8
+    fn read_file(args: &StdFuncArgs) -> StdFuncResult {
+        let path = Self::get_path(args, 1)?;
+        let text = fs::read_to_string(path).map_err(|err| format!("can't read file {}: {}", path, err))?;
+        Ok(Some(ValueVariant::String(text.into())))
+    }
+
+    fn read_bytes(args: &StdFuncArgs) -> StdFuncResult {
+        let path = Self::get_path(args, 1)?;
+        let bytes = fs::read(path).map_err(|err| format!("can't read file {}: {}", path, err))?;
+        Ok(Some(ValueVariant::Bytes(Rc::new(bytes))))
+    }
+
+    /// writes bytes as is and other values as text
+    fn write_file(args: &StdFuncArgs) -> StdFuncResult {
+        let path = Self::get_path(args, 2)?;
+        let result = match &args[1] {
+            ValueVariant::Bytes(bytes) => fs::write(path, &bytes[..]),
+            value => fs::write(path, value.to_string()),
+        };
+        result.map_err(|err| format!("can't write file {}: {}", path, err))?;
+        Ok(None)
     }
 
 
@@ -51,5 +100,9 @@ impl FunctionRepository for IOFunctionRepo {
     fn get_functions(&self) -> super::func_respository::StdFuncMap {
         self.funcs.clone()
     }
+
+    fn get_signatures(&self) -> StdFuncSignatures {
+        self.signatures.clone()
+    }
 }
 
@@ -0,0 +1,486 @@
+use std::{collections::{BTreeMap, HashMap}, rc::Rc};
+use crate::ex_core::ValueVariant;
+
+use super::func_respository::{FunctionRepository, StdFuncResult, StdFuncArgs, StdFuncMap, StdFunc, StdFuncSignatures};
+
+/// arrays and objects nested deeper are rejected, so parsing can't overflow the stack
+const MAX_JSON_DEPTH: usize = 512;
+
+/// parses JSON text: objects are maps, arrays are lists, null is nil,
+/// numbers without fraction and exponent are ints, if they fit into int, otherwise floats
+pub fn parse_json(text: &str) -> Result<ValueVariant, String> {
+    let mut parser = JsonParser { text, pos: 0, depth: 0 };
+    parser.skip_whitespace();
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos < text.len() {
+        return Err(parser.error("unexpected text after JSON value"));
+    }
+    Ok(value)
+}
+
+/// JSON text of the value, pretty text has one item per line indented with 2 spaces
+pub fn stringify_json(value: &ValueVariant, pretty: bool) -> Result<String, String> {
+    let mut out = String::new();
+    write_json(&mut out, value, pretty.then_some(0))?;
+    Ok(out)
+}
+
+struct JsonParser<'a> {
+    text: &'a str,
+    /// byte offset of the next char
+    pos: usize,
+    depth: usize,
+}
+
+impl JsonParser<'_> {
+    fn error(&self, message: &str) -> String {
+        let before = &self.text[..self.pos];
+        let line = before.matches('\n').count() + 1;
+        let col = before.chars().rev().take_while(|ch| *ch != '\n').count() + 1;
+        format!("{} at line {}, column {}", message, line, col)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    /// skips digits, returns false if there are none
+    fn digits(&mut self) -> bool {
+        let start = self.pos;
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.pos += 1;
+        }
+        self.pos > start
+    }
+
+    fn value(&mut self) -> Result<ValueVariant, String> {
+        match self.peek() {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => Ok(ValueVariant::String(self.string()?.into())),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => self.literal(),
+            None => Err(self.error("unexpected end of JSON")),
+        }
+    }
+
+    fn literal(&mut self) -> Result<ValueVariant, String> {
+        for (word, value) in [("true", ValueVariant::Bool(true)), ("false", ValueVariant::Bool(false)), ("null", ValueVariant::Nil)] {
+            if self.text[self.pos..].starts_with(word) {
+                self.pos += word.len();
+                return Ok(value);
+            }
+        }
+        let ch = self.text[self.pos..].chars().next().unwrap_or_default();
+        Err(self.error(&format!("unexpected character {:?}", ch)))
+    }
+
+    fn number(&mut self) -> Result<ValueVariant, String> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        // leading zeros are not allowed
+        match self.peek() {
+            Some(b'0') => self.pos += 1,
+            Some(b'1'..=b'9') => {
+                self.digits();
+            }
+            _ => return Err(self.error("invalid number")),
+        }
+        let mut is_float = false;
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            is_float = true;
+            if !self.digits() {
+                return Err(self.error("invalid number"));
+            }
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.pos += 1;
+            is_float = true;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            if !self.digits() {
+                return Err(self.error("invalid number"));
+            }
+        }
+        let number = &self.text[start..self.pos];
+        if !is_float {
+            // integers, that don't fit into int, are not rounded to floats silently
+            return number.parse::<i64>()
+                .map(ValueVariant::Integer)
+                .map_err(|_| self.error(&format!("integer {} is out of the int range", number)));
+        }
+        match number.parse::<f64>() {
+            Ok(f) if f.is_finite() => Ok(ValueVariant::Float(f)),
+            _ => Err(self.error(&format!("number {} is out of range", number))),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        // opening quote
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            let Some(ch) = self.text[self.pos..].chars().next() else {
+                return Err(self.error("unterminated string"));
+            };
+            match ch {
+                '"' => {
+                    self.pos += 1;
+                    return Ok(s);
+                }
+                '\\' => {
+                    self.pos += 1;
+                    s.push(self.escape()?);
+                }
+                ch if (ch as u32) < 0x20 => return Err(self.error("control character in string")),
+                ch => {
+                    s.push(ch);
+                    self.pos += ch.len_utf8();
+                }
+            }
+        }
+    }
+
+    /// char of the escape sequence after the backslash
+    fn escape(&mut self) -> Result<char, String> {
+        let ch = match self.peek() {
+            Some(b'"') => '"',
+            Some(b'\\') => '\\',
+            Some(b'/') => '/',
+            Some(b'b') => '\u{8}',
+            Some(b'f') => '\u{c}',
+            Some(b'n') => '\n',
+            Some(b'r') => '\r',
+            Some(b't') => '\t',
+            Some(b'u') => {
+                self.pos += 1;
+                return self.unicode_escape();
+            }
+            Some(_) => return Err(self.error("invalid escape sequence")),
+            None => return Err(self.error("unterminated string")),
+        };
+        self.pos += 1;
+        Ok(ch)
+    }
+
+    /// char of \uXXXX, chars outside of the basic plane are written as a surrogate pair \uD83D\uDE00
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.hex4()?;
+        let code = if (0xD800..0xDC00).contains(&high) {
+            if !self.text[self.pos..].starts_with("\\u") {
+                return Err(self.error("unpaired surrogate in unicode escape"));
+            }
+            self.pos += 2;
+            let low = self.hex4()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(self.error("unpaired surrogate in unicode escape"));
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or_else(|| self.error("unpaired surrogate in unicode escape"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let code = self.text.get(self.pos..self.pos + 4)
+            .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        self.pos += 4;
+        Ok(code)
+    }
+
+    fn enter(&mut self) -> Result<(), String> {
+        self.depth += 1;
+        if self.depth > MAX_JSON_DEPTH {
+            return Err(self.error(&format!("JSON is nested deeper than {} levels", MAX_JSON_DEPTH)));
+        }
+        // opening bracket
+        self.pos += 1;
+        self.skip_whitespace();
+        Ok(())
+    }
+
+    /// skips the separator after the item, returns true after the closing bracket
+    fn item_end(&mut self, close: u8) -> Result<bool, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b',') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                Ok(false)
+            }
+            Some(b) if b == close => {
+                self.pos += 1;
+                self.depth -= 1;
+                Ok(true)
+            }
+            _ => Err(self.error(&format!("expected ',' or '{}'", close as char))),
+        }
+    }
+
+    fn array(&mut self) -> Result<ValueVariant, String> {
+        self.enter()?;
+        let mut items = Vec::new();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            self.depth -= 1;
+            return Ok(ValueVariant::List(Rc::new(items)));
+        }
+        loop {
+            items.push(self.value()?);
+            if self.item_end(b']')? {
+                return Ok(ValueVariant::List(Rc::new(items)));
+            }
+        }
+    }
+
+    /// the last value of the repeated key is kept
+    fn object(&mut self) -> Result<ValueVariant, String> {
+        self.enter()?;
+        let mut map = BTreeMap::new();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            self.depth -= 1;
+            return Ok(ValueVariant::Map(Rc::new(map)));
+        }
+        loop {
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected string key"));
+            }
+            let key = self.string()?;
+            self.skip_whitespace();
+            if self.peek() != Some(b':') {
+                return Err(self.error("expected ':'"));
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+            map.insert(key, self.value()?);
+            if self.item_end(b'}')? {
+                return Ok(ValueVariant::Map(Rc::new(map)));
+            }
+        }
+    }
+}
+
+/// 'indent' is the nesting level of the pretty text or None for the compact text
+fn write_json(out: &mut String, value: &ValueVariant, indent: Option<usize>) -> Result<(), String> {
+    match value {
+        ValueVariant::Nil => out.push_str("null"),
+        ValueVariant::Bool(b) => out.push_str(&b.to_string()),
+        ValueVariant::Integer(i) => out.push_str(&i.to_string()),
+        // debug format keeps the fraction of whole floats, so they are parsed back as floats
+        ValueVariant::Float(f) if f.is_finite() => out.push_str(&format!("{:?}", f)),
+        ValueVariant::Float(f) => return Err(format!("float {} can't be written to JSON", f)),
+        ValueVariant::String(s) => write_json_string(out, s),
+        ValueVariant::Char(c) => write_json_string(out, c.encode_utf8(&mut [0; 4])),
+        ValueVariant::List(items) => {
+            write_json_items(out, ('[', ']'), items.iter().map(|item| (None, item)), indent)?;
+        }
+        ValueVariant::Map(map) => {
+            write_json_items(out, ('{', '}'), map.iter().map(|(key, item)| (Some(key.as_str()), item)), indent)?;
+        }
+        ValueVariant::Bytes(_) | ValueVariant::Process(_) => {
+            return Err(format!("{} value can't be written to JSON", value.type_name()));
+        }
+    }
+    Ok(())
+}
+
+/// items of the array or the object, items of the object have keys
+fn write_json_items<'a>(out: &mut String, brackets: (char, char), items: impl Iterator<Item = (Option<&'a str>, &'a ValueVariant)>,
+                        indent: Option<usize>) -> Result<(), String> {
+    out.push(brackets.0);
+    let mut empty = true;
+    for (key, item) in items {
+        if !empty {
+            out.push(',');
+        }
+        empty = false;
+        if let Some(indent) = indent {
+            out.push('\n');
+            out.push_str(&"  ".repeat(indent + 1));
+        }
+        if let Some(key) = key {
+            write_json_string(out, key);
+            out.push_str(if indent.is_some() { ": " } else { ":" });
+        }
+        write_json(out, item, indent.map(|indent| indent + 1))?;
+    }
+    if let (Some(indent), false) = (indent, empty) {
+        out.push('\n');
+        out.push_str(&"  ".repeat(indent));
+    }
+    out.push(brackets.1);
+    Ok(())
+}
+
+fn write_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+}
+
+pub struct JsonFunctionRepo {
+    funcs : StdFuncMap,
+    signatures : StdFuncSignatures,
+}
+
+impl JsonFunctionRepo {
+    pub fn new() -> Self {
+        let mut funcs : HashMap<String, StdFunc> = HashMap::new();
+        funcs.insert("json_parse".to_string(), JsonFunctionRepo::json_parse);
+        funcs.insert("json_stringify".to_string(), JsonFunctionRepo::json_stringify);
+        let mut signatures = StdFuncSignatures::new();
+        signatures.insert("json_parse".to_string(), "json_parse(str)".to_string());
+        signatures.insert("json_stringify".to_string(), "json_stringify(value, [pretty])".to_string());
+        Self {
+            funcs,
+            signatures
+        }
+    }
+
+    fn json_parse(args: &StdFuncArgs) -> StdFuncResult {
+        match args.as_slice() {
+            [ValueVariant::String(text)] => Ok(Some(parse_json(text)?)),
+            [v] => Err(format!("expected JSON string, find {}", v)),
+            _ => Err(format!("expected 1 argument, find {}", args.len()))
+        }
+    }
+
+    /// json_stringify(value) or json_stringify(value, pretty), the text is pretty by default
+    fn json_stringify(args: &StdFuncArgs) -> StdFuncResult {
+        let (value, pretty) = match args.as_slice() {
+            [value] => (value, true),
+            [value, ValueVariant::Bool(pretty)] => (value, *pretty),
+            [_, v] => return Err(format!("expected bool pretty argument, find {}", v)),
+            _ => return Err(format!("expected 1 or 2 arguments, find {}", args.len()))
+        };
+        Ok(Some(ValueVariant::String(stringify_json(value, pretty)?.into())))
+    }
+}
+
+impl FunctionRepository for JsonFunctionRepo {
+    fn get_functions(&self) -> StdFuncMap {
+        self.funcs.clone()
+    }
+
+    fn get_signatures(&self) -> StdFuncSignatures {
+        self.signatures.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn parse_test() {
+        let value = parse_json(" {\"a\": [1, -2.5, 1e3, true, null], \"b\": {}, \"c\": [], \"a\": \"last\"} ").unwrap();
+        assert_eq!(value.to_string(), "{\"a\": \"last\", \"b\": {}, \"c\": []}");
+        let value = parse_json("[0, -0, 1.0, 2E-2, 9223372036854775807, -9223372036854775808, 9223372036854775808.0]").unwrap();
+        assert_eq!(value, ValueVariant::List(Rc::new(vec![
+            ValueVariant::Integer(0),
+            ValueVariant::Integer(0),
+            ValueVariant::Float(1.0),
+            ValueVariant::Float(0.02),
+            ValueVariant::Integer(i64::MAX),
+            ValueVariant::Integer(i64::MIN),
+            ValueVariant::Float(9223372036854775808.0),
+        ])));
+        let value = parse_json("\"q\\\" \\\\ \\/ \\b\\f\\n\\r\\t \\u00e9 \\u20AC \\ud83d\\ude00 ñ\"").unwrap();
+        assert_eq!(value, ValueVariant::String("q\" \\ / \u{8}\u{c}\n\r\t é € 😀 ñ".into()));
+    }
+
+    #[test]
+    fn parse_error_test() {
+        let tests = [
+            ("", "unexpected end of JSON at line 1, column 1"),
+            ("[1, 2", "expected ',' or ']' at line 1, column 6"),
+            ("{\"a\" 1}", "expected ':' at line 1, column 6"),
+            ("{a: 1}", "expected string key at line 1, column 2"),
+            ("[1,\n tru]", "unexpected character 't' at line 2, column 2"),
+            ("[01]", "expected ',' or ']' at line 1, column 3"),
+            ("1.", "invalid number at line 1, column 3"),
+            ("1e999", "number 1e999 is out of range at line 1, column 6"),
+            ("12345678901234567891", "integer 12345678901234567891 is out of the int range at line 1, column 21"),
+            ("[-9223372036854775809]", "integer -9223372036854775809 is out of the int range at line 1, column 22"),
+            ("\"a", "unterminated string at line 1, column 3"),
+            ("\"\\x\"", "invalid escape sequence at line 1, column 3"),
+            ("\"\\u12\"", "invalid unicode escape at line 1, column 4"),
+            ("\"\\ud83d\"", "unpaired surrogate in unicode escape at line 1, column 8"),
+            ("\"\\ude00\"", "unpaired surrogate in unicode escape at line 1, column 8"),
+            ("\"a\nb\"", "control character in string at line 1, column 3"),
+            ("1 2", "unexpected text after JSON value at line 1, column 3"),
+        ];
+        for (text, err) in tests {
+            assert_eq!(parse_json(text).unwrap_err(), err, "{}", text);
+        }
+        let deep = "[".repeat(MAX_JSON_DEPTH + 1);
+        assert_eq!(parse_json(&deep).unwrap_err(), format!("JSON is nested deeper than 512 levels at line 1, column {}", MAX_JSON_DEPTH + 1));
+        assert!(parse_json(&("[".repeat(MAX_JSON_DEPTH) + &"]".repeat(MAX_JSON_DEPTH))).is_ok());
+    }
+
+    #[test]
+    fn stringify_test() {
+        let value = parse_json("{\"name\": \"ex\\n\\u0001\", \"items\": [1, 2.0, {}, [], null, false], \"e\": {\"x\": 1}}").unwrap();
+        assert_eq!(stringify_json(&value, false).unwrap(), "{\"e\":{\"x\":1},\"items\":[1,2.0,{},[],null,false],\"name\":\"ex\\n\\u0001\"}");
+        assert_eq!(stringify_json(&value, true).unwrap(), "{\n  \"e\": {\n    \"x\": 1\n  },\n  \"items\": [\n    1,\n    2.0,\n    {},\n    [],\n    null,\n    false\n  ],\n  \"name\": \"ex\\n\\u0001\"\n}");
+        assert_eq!(stringify_json(&ValueVariant::Char('é'), true).unwrap(), "\"é\"");
+        assert_eq!(stringify_json(&ValueVariant::Float(f64::NAN), true).unwrap_err(), "float NaN can't be written to JSON");
+        let bytes = ValueVariant::List(Rc::new(vec![ValueVariant::Bytes(Rc::new(vec![1]))]));
+        assert_eq!(stringify_json(&bytes, true).unwrap_err(), "bytes value can't be written to JSON");
+        // big integers are written back with the same digits
+        let big = "[9223372036854775807,-9223372036854775808,1234567890123456789]";
+        assert_eq!(stringify_json(&parse_json(big).unwrap(), false).unwrap(), big);
+
+        let args = vec![ValueVariant::List(Rc::new(vec![ValueVariant::Integer(1)])), ValueVariant::Bool(false)];
+        assert_eq!(JsonFunctionRepo::json_stringify(&args).unwrap(), Some(ValueVariant::String("[1]".into())));
+        assert_eq!(JsonFunctionRepo::json_stringify(&args[..1].to_vec()).unwrap(), Some(ValueVariant::String("[\n  1\n]".into())));
+    }
+
+    fn json_value() -> impl Strategy<Value = ValueVariant> {
+        let leaf = prop_oneof![
+            Just(ValueVariant::Nil),
+            any::<bool>().prop_map(ValueVariant::Bool),
+            any::<i64>().prop_map(ValueVariant::Integer),
+            any::<f64>().prop_filter("finite", |f| f.is_finite()).prop_map(ValueVariant::Float),
+            any::<String>().prop_map(|s| ValueVariant::String(s.into())),
+        ];
+        leaf.prop_recursive(4, 32, 6, |inner| prop_oneof![
+            proptest::collection::vec(inner.clone(), 0..6).prop_map(|items| ValueVariant::List(Rc::new(items))),
+            proptest::collection::btree_map(any::<String>(), inner, 0..6).prop_map(|map| ValueVariant::Map(Rc::new(map))),
+        ])
+    }
+
+    proptest! {
+        #[test]
+        fn json_round_trip(value in json_value(), pretty in any::<bool>()) {
+            let text = stringify_json(&value, pretty).unwrap();
+            prop_assert_eq!(parse_json(&text).unwrap(), value);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::rc::Rc;
+
+use crate::ex_core::ValueVariant;
+
+use super::method_repository::{StdMethodsMap, StdMutMethodsMap, StdMethodsRepository, StdMethodArgs, StdMethodResult};
+
+
+pub struct ListMethods {
+    methods: StdMethodsMap,
+    mut_methods: StdMutMethodsMap,
+}
+
+impl ListMethods {
+    pub fn new() -> Self {
+        let mut methods = StdMethodsMap::new();
+        methods.insert("len".to_string(), ListMethods::len);
+        methods.insert("get".to_string(), ListMethods::get);
+        let mut mut_methods = StdMutMethodsMap::new();
+        mut_methods.insert("push".to_string(), ListMethods::push);
+        mut_methods.insert("pop".to_string(), ListMethods::pop);
+        Self {
+            methods,
+            mut_methods,
+        }
+    }
+
+    fn get_list(v: &ValueVariant) -> Result<&Vec<ValueVariant>, String> {
+        match v {
+            ValueVariant::List(l) => Ok(l),
+            _ => Err(format!("exptected list, find {}", v))
+        }
+    }
+
+    /// the list is copied, if it's shared with other values
+    fn get_list_mut(v: &mut ValueVariant) -> Result<&mut Vec<ValueVariant>, String> {
+        match v {
+            ValueVariant::List(l) => Ok(Rc::make_mut(l)),
+            _ => Err(format!("exptected list, find {}", v))
+        }
+    }
+
+    fn len(this: &ValueVariant, args: &StdMethodArgs) -> StdMethodResult {
+        if !args.is_empty() {
+            return Err(String::from("method arg expected 0 arguments"));
+        }
+        let this_l = Self::get_list(this)?;
+        Ok(Some(ValueVariant::Integer(this_l.len() as i64)))
+    }
+
+    fn get(this: &ValueVariant, args: &StdMethodArgs) -> StdMethodResult {
+        if args.len() != 1 {
+            return Err(format!("method arg expected 1 argument, find {}", args.len()));
+        }
+        let this_l = Self::get_list(this)?;
+        match &args[0] {
+            ValueVariant::Integer(i) if *i >= 0 && (*i as usize) < this_l.len() => {
+                Ok(Some(this_l[*i as usize].clone()))
+            }
+            ValueVariant::Integer(i) => {
+                Err(format!("index {} out of range for list of length {}", i, this_l.len()))
+            }
+            v => Err(format!("exptected int index, find {}", v))
+        }
+    }
+
+    fn push(this: &mut ValueVariant, args: &StdMethodArgs) -> StdMethodResult {
+        if args.len() != 1 {
+            return Err(format!("method arg expected 1 argument, find {}", args.len()));
+        }
+        Self::get_list_mut(this)?.push(args[0].clone());
+        Ok(None)
+    }
+
+    /// removes the last item and returns it, or nil if the list is empty
+    fn pop(this: &mut ValueVariant, args: &StdMethodArgs) -> StdMethodResult {
+        if !args.is_empty() {
+            return Err(String::from("method arg expected 0 arguments"));
+        }
+        match this {
+            ValueVariant::List(l) if l.is_empty() => Ok(None),
+            _ => Ok(Self::get_list_mut(this)?.pop()),
+        }
+    }
+}
+
+impl StdMethodsRepository for ListMethods {
+    fn get_type_name(&self) -> &'static str {
+        "list"
+    }
+
+    fn get_methods(&self) -> StdMethodsMap {
+        self.methods.clone()
+    }
+
+    fn get_mut_methods(&self) -> StdMutMethodsMap {
+        self.mut_methods.clone()
+    }
+}
@@ -0,0 +1,108 @@
+use std::rc::Rc;
+
+use crate::ex_core::ValueVariant;
+
+use super::method_repository::{StdMethodsMap, StdMutMethodsMap, StdMethodsRepository, StdMethodArgs, StdMethodResult};
+
+
+pub struct MapMethods {
+    methods: StdMethodsMap,
+    mut_methods: StdMutMethodsMap,
+}
+
+impl MapMethods {
+    pub fn new() -> Self {
+        let mut methods = StdMethodsMap::new();
+        methods.insert("len".to_string(), MapMethods::len);
+        methods.insert("get".to_string(), MapMethods::get);
+        methods.insert("contains".to_string(), MapMethods::contains);
+        methods.insert("keys".to_string(), MapMethods::keys);
+        let mut mut_methods = StdMutMethodsMap::new();
+        mut_methods.insert("remove".to_string(), MapMethods::remove);
+        Self {
+            methods,
+            mut_methods,
+        }
+    }
+
+    fn get_map(v: &ValueVariant) -> Result<&std::collections::BTreeMap<String, ValueVariant>, String> {
+        match v {
+            ValueVariant::Map(m) => Ok(m),
+            _ => Err(format!("exptected map, find {}", v))
+        }
+    }
+
+    fn get_key(v: &ValueVariant) -> Result<&str, String> {
+        match v {
+            ValueVariant::String(s) => Ok(s),
+            _ => Err(format!("exptected string key, find {}", v))
+        }
+    }
+
+    fn len(this: &ValueVariant, args: &StdMethodArgs) -> StdMethodResult {
+        if !args.is_empty() {
+            return Err(String::from("method arg expected 0 arguments"));
+        }
+        let this_m = Self::get_map(this)?;
+        Ok(Some(ValueVariant::Integer(this_m.len() as i64)))
+    }
+
+    /// get(key) or get(key, default)
+    fn get(this: &ValueVariant, args: &StdMethodArgs) -> StdMethodResult {
+        if args.is_empty() || args.len() > 2 {
+            return Err(format!("method arg expected 1 or 2 arguments, find {}", args.len()));
+        }
+        let this_m = Self::get_map(this)?;
+        let key = Self::get_key(&args[0])?;
+        match (this_m.get(key), args.get(1)) {
+            (Some(value), _) => Ok(Some(value.clone())),
+            (None, Some(default)) => Ok(Some(default.clone())),
+            (None, None) => Err(format!("no key \"{}\" in map", key))
+        }
+    }
+
+    fn contains(this: &ValueVariant, args: &StdMethodArgs) -> StdMethodResult {
+        if args.len() != 1 {
+            return Err(format!("method arg expected 1 argument, find {}", args.len()));
+        }
+        let this_m = Self::get_map(this)?;
+        let key = Self::get_key(&args[0])?;
+        Ok(Some(ValueVariant::Bool(this_m.contains_key(key))))
+    }
+
+    fn keys(this: &ValueVariant, args: &StdMethodArgs) -> StdMethodResult {
+        if !args.is_empty() {
+            return Err(String::from("method arg expected 0 arguments"));
+        }
+        let this_m = Self::get_map(this)?;
+        Ok(Some(ValueVariant::List(Rc::new(this_m.keys().map(|key| ValueVariant::String(key.as_str().into())).collect()))))
+    }
+
+    /// removes the key and returns its value, or nil if there is no such key
+    fn remove(this: &mut ValueVariant, args: &StdMethodArgs) -> StdMethodResult {
+        if args.len() != 1 {
+            return Err(format!("method arg expected 1 argument, find {}", args.len()));
+        }
+        let key = Self::get_key(&args[0])?;
+        match this {
+            // the shared map isn't copied, if there is nothing to remove
+            ValueVariant::Map(m) if !m.contains_key(key) => Ok(None),
+            ValueVariant::Map(m) => Ok(Rc::make_mut(m).remove(key)),
+            _ => Err(format!("exptected map, find {}", this))
+        }
+    }
+}
+
+impl StdMethodsRepository for MapMethods {
+    fn get_type_name(&self) -> &'static str {
+        "map"
+    }
+
+    fn get_methods(&self) -> StdMethodsMap {
+        self.methods.clone()
+    }
+
+    fn get_mut_methods(&self) -> StdMutMethodsMap {
+        self.mut_methods.clone()
+    }
+}
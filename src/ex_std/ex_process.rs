@@ -0,0 +1,443 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt,
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, ExitStatus, Stdio},
+    rc::Rc,
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+use crate::ex_core::ValueVariant;
+
+use super::ex_expand::expand_word;
+use super::func_respository::{FunctionRepository, StdFuncResult, StdFuncArgs, StdFuncMap, StdFunc, StdFuncSignatures};
+use super::method_repository::{StdMethodsMap, StdMethodsRepository, StdMethodArgs, StdMethodResult};
+
+pub type ProcessRef = Rc<RefCell<ProcessHandle>>;
+
+/// Child process started by 'spawn'.
+/// Stdout of the child is read line by line in a separate thread,
+/// so the child never blocks on a full pipe while the script is busy.
+/// Lines are raw bytes with the line end, they are decoded only by stdout_lines().
+pub struct ProcessHandle {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout_lines: Receiver<Vec<u8>>,
+    started: Instant,
+    timeout: Option<Duration>,
+    /// the child is killed, when the handle is dropped
+    kill_on_drop: bool,
+    exit_code: Option<i64>,
+}
+
+/// options of 'spawn'
+#[derive(Default)]
+struct SpawnOptions {
+    timeout: Option<Duration>,
+    kill_on_drop: bool,
+}
+
+impl ProcessHandle {
+    pub fn pid(&self) -> u32 {
+        self.child.id()
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        self.timeout.map(|timeout| self.started + timeout)
+    }
+
+    fn kill_on_timeout(&mut self) -> String {
+        let _ = self.child.kill();
+        if let Ok(status) = self.child.wait() {
+            self.exit_code = Some(exit_status_to_code(status));
+        }
+        format!("process {} timed out after {:?}", self.pid(), self.timeout.unwrap_or_default())
+    }
+
+    fn wait(&mut self) -> Result<i64, String> {
+        if let Some(code) = self.exit_code {
+            return Ok(code);
+        }
+        // close stdin, so the child doesn't wait for input forever
+        self.stdin = None;
+        let status = match self.deadline() {
+            None => self.child.wait().map_err(|err| err.to_string())?,
+            Some(deadline) => loop {
+                if let Some(status) = self.child.try_wait().map_err(|err| err.to_string())? {
+                    break status;
+                }
+                if Instant::now() >= deadline {
+                    return Err(self.kill_on_timeout());
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+        };
+        let code = exit_status_to_code(status);
+        self.exit_code = Some(code);
+        Ok(code)
+    }
+
+    fn read_stdout_lines(&mut self) -> Result<Vec<Vec<u8>>, String> {
+        let mut lines = Vec::new();
+        loop {
+            let line = match self.deadline() {
+                None => match self.stdout_lines.recv() {
+                    Ok(line) => line,
+                    Err(_) => break
+                },
+                Some(deadline) => match self.stdout_lines.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    Ok(line) => line,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                    Err(RecvTimeoutError::Timeout) => return Err(self.kill_on_timeout())
+                }
+            };
+            lines.push(line);
+        }
+        Ok(lines)
+    }
+
+    fn write_stdin(&mut self, input: &[u8]) -> Result<(), String> {
+        if let Some(stdin) = self.stdin.as_mut() {
+            stdin.write_all(input).and_then(|_| stdin.flush()).map_err(|err| err.to_string())
+        } else {
+            Err(format!("stdin of process {} is closed", self.pid()))
+        }
+    }
+
+    fn kill(&mut self, signal: Option<i32>) -> Result<(), String> {
+        if self.exit_code.is_some() {
+            return Ok(());
+        }
+        match signal {
+            None => self.child.kill().map_err(|err| err.to_string()),
+            Some(signal) => send_signal(self.pid(), signal)
+        }
+    }
+}
+
+/// the child, that has already exited, is reaped, so it's not left as a zombie,
+/// the running child keeps running, unless it's spawned with the 'kill_on_drop' option
+impl Drop for ProcessHandle {
+    fn drop(&mut self) {
+        if self.exit_code.is_some() {
+            return;
+        }
+        self.stdin = None;
+        if let Ok(None) = self.child.try_wait() {
+            if self.kill_on_drop && self.child.kill().is_ok() {
+                let _ = self.child.wait();
+            }
+        }
+    }
+}
+
+impl PartialEq for ProcessHandle {
+    fn eq(&self, other: &Self) -> bool {
+        self.pid() == other.pid()
+    }
+}
+
+impl fmt::Debug for ProcessHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProcessHandle").field("pid", &self.pid()).finish()
+    }
+}
+
+#[cfg(unix)]
+fn exit_status_to_code(status: ExitStatus) -> i64 {
+    use std::os::unix::process::ExitStatusExt;
+    // shell convention for processes terminated by a signal
+    status.code().map(i64::from).or(status.signal().map(|s| 128 + s as i64)).unwrap_or(-1)
+}
+
+#[cfg(not(unix))]
+fn exit_status_to_code(status: ExitStatus) -> i64 {
+    status.code().map(i64::from).unwrap_or(-1)
+}
+
+#[cfg(unix)]
+fn signal_by_name(name: &str) -> Option<i32> {
+    let name = name.strip_prefix("SIG").unwrap_or(name);
+    match name {
+        "HUP" => Some(libc::SIGHUP),
+        "INT" => Some(libc::SIGINT),
+        "QUIT" => Some(libc::SIGQUIT),
+        "KILL" => Some(libc::SIGKILL),
+        "USR1" => Some(libc::SIGUSR1),
+        "USR2" => Some(libc::SIGUSR2),
+        "TERM" => Some(libc::SIGTERM),
+        "CONT" => Some(libc::SIGCONT),
+        "STOP" => Some(libc::SIGSTOP),
+        _ => None
+    }
+}
+
+#[cfg(not(unix))]
+fn signal_by_name(name: &str) -> Option<i32> {
+    match name.strip_prefix("SIG").unwrap_or(name) {
+        "KILL" => Some(9),
+        _ => None
+    }
+}
+
+#[cfg(unix)]
+fn send_signal(pid: u32, signal: i32) -> Result<(), String> {
+    // SAFETY: kill has no memory safety preconditions
+    if unsafe { libc::kill(pid as libc::pid_t, signal) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error().to_string())
+    }
+}
+
+#[cfg(not(unix))]
+fn send_signal(_pid: u32, signal: i32) -> Result<(), String> {
+    Err(format!("signal {} is not supported on this platform, use kill() without args", signal))
+}
+
+pub struct ProcessFunctionRepo {
+    funcs : StdFuncMap,
+    signatures : StdFuncSignatures,
+}
+impl ProcessFunctionRepo {
+    pub fn new() -> Self {
+        let mut funcs : HashMap<String, StdFunc> = HashMap::new();
+        funcs.insert("spawn".to_string(), ProcessFunctionRepo::spawn);
+        let mut signatures = StdFuncSignatures::new();
+        signatures.insert("spawn".to_string(), "spawn(cmd, [args], [opts])".to_string());
+        Self {
+            funcs,
+            signatures
+        }
+    }
+
+    fn get_options(opts: &ValueVariant) -> Result<SpawnOptions, String> {
+        let opts = match opts {
+            ValueVariant::Map(m) => m,
+            _ => return Err(format!("expected map of spawn options, find {}", opts))
+        };
+        let mut options = SpawnOptions::default();
+        for (key, value) in opts.iter() {
+            match (key.as_str(), value) {
+                ("timeout", ValueVariant::Integer(i)) if *i >= 0 => options.timeout = Some(Duration::from_secs(*i as u64)),
+                ("timeout", ValueVariant::Float(f)) if *f >= 0.0 => options.timeout = Some(Duration::from_secs_f64(*f)),
+                ("timeout", _) => return Err(format!("not valid timeout {}", value)),
+                ("kill_on_drop", ValueVariant::Bool(b)) => options.kill_on_drop = *b,
+                ("kill_on_drop", _) => return Err(format!("not valid kill_on_drop {}, expected bool", value)),
+                _ => return Err(format!("unknown spawn option '{}'", key))
+            }
+        }
+        Ok(options)
+    }
+
+    /// strings are expanded with expand_word, nested lists are spliced into args
+    fn build_args(list: &[ValueVariant], cmd_args: &mut Vec<String>) -> Result<(), String> {
+        for arg in list.iter() {
+            match arg {
+                ValueVariant::String(s) => cmd_args.extend(expand_word(s)?),
+                ValueVariant::List(l) => Self::build_args(l, cmd_args)?,
+                ValueVariant::Map(_) | ValueVariant::Process(_) | ValueVariant::Nil => {
+                    return Err(format!("not valid command argument {}", arg));
+                }
+                _ => cmd_args.push(arg.to_string())
+            }
+        }
+        Ok(())
+    }
+
+    /// spawn(cmd), spawn(cmd, args) or spawn(cmd, args, opts)
+    /// starts 'cmd' as a child process and returns a process handle,
+    /// supported opts: 'timeout' in seconds for wait() and stdout_lines(),
+    /// 'kill_on_drop' kills the child, when the script no longer uses its handle
+    fn spawn(args: &StdFuncArgs) -> StdFuncResult {
+        if args.is_empty() || args.len() > 3 {
+            return Err(format!("expected 1 to 3 arguments, find {}", args.len()));
+        }
+        let cmd = match &args[0] {
+            ValueVariant::String(s) => {
+                let mut words = expand_word(s)?;
+                if words.len() != 1 {
+                    return Err(format!("command '{}' expands to {} words", s, words.len()));
+                }
+                words.remove(0)
+            }
+            v => return Err(format!("expected command string, find {}", v))
+        };
+        let mut cmd_args = Vec::new();
+        if let Some(list) = args.get(1) {
+            match list {
+                ValueVariant::List(l) => Self::build_args(l, &mut cmd_args)?,
+                v => return Err(format!("expected list of command args, find {}", v))
+            }
+        }
+        let options = match args.get(2) {
+            Some(opts) => Self::get_options(opts)?,
+            None => SpawnOptions::default()
+        };
+
+        let mut child = Command::new(&cmd)
+            .args(&cmd_args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|err| format!("can't spawn '{}': {}", cmd, err))?;
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take().expect("stdout of child process should be piped");
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(stdout);
+            let mut line = Vec::new();
+            loop {
+                line.clear();
+                match reader.read_until(b'\n', &mut line) {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => {
+                        if sender.send(line.clone()).is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+        let handle = ProcessHandle {
+            child,
+            stdin,
+            stdout_lines: receiver,
+            started: Instant::now(),
+            timeout: options.timeout,
+            kill_on_drop: options.kill_on_drop,
+            exit_code: None
+        };
+        Ok(Some(ValueVariant::Process(Rc::new(RefCell::new(handle)))))
+    }
+}
+
+impl FunctionRepository for ProcessFunctionRepo {
+    fn get_functions(&self) -> StdFuncMap {
+        self.funcs.clone()
+    }
+
+    fn get_signatures(&self) -> StdFuncSignatures {
+        self.signatures.clone()
+    }
+}
+
+pub struct ProcessMethods {
+    methods: StdMethodsMap
+}
+
+impl ProcessMethods {
+    pub fn new() -> Self {
+        let mut methods = StdMethodsMap::new();
+        methods.insert("pid".to_string(), ProcessMethods::pid);
+        methods.insert("wait".to_string(), ProcessMethods::wait);
+        methods.insert("kill".to_string(), ProcessMethods::kill);
+        methods.insert("stdout_lines".to_string(), ProcessMethods::stdout_lines);
+        methods.insert("stdout_bytes".to_string(), ProcessMethods::stdout_bytes);
+        methods.insert("write_stdin".to_string(), ProcessMethods::write_stdin);
+        methods.insert("close_stdin".to_string(), ProcessMethods::close_stdin);
+        Self {
+            methods
+        }
+    }
+
+    fn get_process(v: &ValueVariant) -> Result<&ProcessRef, String> {
+        match v {
+            ValueVariant::Process(p) => Ok(p),
+            _ => Err(format!("exptected process, find {}", v))
+        }
+    }
+
+    fn pid(this: &ValueVariant, args: &StdMethodArgs) -> StdMethodResult {
+        if !args.is_empty() {
+            return Err(String::from("method arg expected 0 arguments"));
+        }
+        let process = Self::get_process(this)?;
+        let pid = process.borrow().pid();
+        Ok(Some(ValueVariant::Integer(pid as i64)))
+    }
+
+    /// waits for the process to exit and returns its exit code
+    fn wait(this: &ValueVariant, args: &StdMethodArgs) -> StdMethodResult {
+        if !args.is_empty() {
+            return Err(String::from("method arg expected 0 arguments"));
+        }
+        let process = Self::get_process(this)?;
+        let code = process.borrow_mut().wait()?;
+        Ok(Some(ValueVariant::Integer(code)))
+    }
+
+    /// kill() kills the process, kill(signal) sends signal by number or name ("TERM", "SIGINT", ...)
+    fn kill(this: &ValueVariant, args: &StdMethodArgs) -> StdMethodResult {
+        let signal = match args.as_slice() {
+            [] => None,
+            [ValueVariant::Integer(i)] => Some(i32::try_from(*i).map_err(|_| format!("not valid signal {}", i))?),
+            [ValueVariant::String(s)] => Some(signal_by_name(s).ok_or(format!("unknown signal '{}'", s))?),
+            [v] => return Err(format!("expected signal number or name, find {}", v)),
+            _ => return Err(format!("method arg expected 0 or 1 argument, find {}", args.len()))
+        };
+        let process = Self::get_process(this)?;
+        process.borrow_mut().kill(signal)?;
+        Ok(None)
+    }
+
+    /// reads stdout of the process until it is closed, returns list of lines
+    fn stdout_lines(this: &ValueVariant, args: &StdMethodArgs) -> StdMethodResult {
+        if !args.is_empty() {
+            return Err(String::from("method arg expected 0 arguments"));
+        }
+        let process = Self::get_process(this)?;
+        let lines = process.borrow_mut().read_stdout_lines()?;
+        let lines = lines.iter().map(|line| {
+            // output, that is not valid utf-8, is decoded lossily, stdout_bytes() returns it as is
+            let text = String::from_utf8_lossy(line);
+            let text = text.strip_suffix('\n').unwrap_or(&text);
+            let text = text.strip_suffix('\r').unwrap_or(text);
+            ValueVariant::String(text.into())
+        });
+        Ok(Some(ValueVariant::List(Rc::new(lines.collect()))))
+    }
+
+    /// reads stdout of the process until it is closed, returns raw bytes of the output
+    fn stdout_bytes(this: &ValueVariant, args: &StdMethodArgs) -> StdMethodResult {
+        if !args.is_empty() {
+            return Err(String::from("method arg expected 0 arguments"));
+        }
+        let process = Self::get_process(this)?;
+        let lines = process.borrow_mut().read_stdout_lines()?;
+        Ok(Some(ValueVariant::Bytes(Rc::new(lines.concat()))))
+    }
+
+    fn write_stdin(this: &ValueVariant, args: &StdMethodArgs) -> StdMethodResult {
+        if args.len() != 1 {
+            return Err(format!("method arg expected 1 argument, find {}", args.len()));
+        }
+        let process = Self::get_process(this)?;
+        match &args[0] {
+            ValueVariant::Bytes(bytes) => process.borrow_mut().write_stdin(bytes)?,
+            value => process.borrow_mut().write_stdin(value.to_string().as_bytes())?,
+        }
+        Ok(None)
+    }
+
+    fn close_stdin(this: &ValueVariant, args: &StdMethodArgs) -> StdMethodResult {
+        if !args.is_empty() {
+            return Err(String::from("method arg expected 0 arguments"));
+        }
+        let process = Self::get_process(this)?;
+        process.borrow_mut().stdin = None;
+        Ok(None)
+    }
+}
+
+impl StdMethodsRepository for ProcessMethods {
+    fn get_type_name(&self) -> &'static str {
+        "process"
+    }
+
+    fn get_methods(&self) -> StdMethodsMap {
+        self.methods.clone()
+    }
+}
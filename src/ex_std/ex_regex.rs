@@ -0,0 +1,150 @@
+use std::{collections::{BTreeMap, HashMap}, rc::Rc};
+use regex::{Captures, Regex};
+use crate::ex_core::ValueVariant;
+
+use super::func_respository::{StdFuncResult, StdFuncArgs, StdFuncSignatures};
+
+/// compiled patterns are dropped, when the cache has so many of them
+const REGEX_CACHE_SIZE: usize = 256;
+
+/// compiled regular expressions by their patterns
+#[derive(Default)]
+pub struct RegexCache {
+    regexes: HashMap<String, Regex>,
+}
+
+impl RegexCache {
+    fn get(&mut self, pattern: &str) -> Result<&Regex, String> {
+        if !self.regexes.contains_key(pattern) {
+            let regex = Regex::new(pattern).map_err(|err| format!("invalid pattern '{}': {}", pattern, err))?;
+            if self.regexes.len() >= REGEX_CACHE_SIZE {
+                self.regexes.clear();
+            }
+            self.regexes.insert(pattern.to_string(), regex);
+        }
+        Ok(&self.regexes[pattern])
+    }
+}
+
+/// std function, that uses patterns compiled by the interpreter
+pub type StdRegexFunc = fn(&mut RegexCache, &StdFuncArgs) -> StdFuncResult;
+pub type StdRegexFuncMap = HashMap<String, StdRegexFunc>;
+
+pub struct RegexFunctionRepo {
+    funcs : StdRegexFuncMap,
+    signatures : StdFuncSignatures,
+}
+
+impl RegexFunctionRepo {
+    pub fn new() -> Self {
+        let mut funcs = StdRegexFuncMap::new();
+        funcs.insert("re_match".to_string(), RegexFunctionRepo::re_match);
+        funcs.insert("re_find_all".to_string(), RegexFunctionRepo::re_find_all);
+        funcs.insert("re_replace".to_string(), RegexFunctionRepo::re_replace);
+        funcs.insert("re_split".to_string(), RegexFunctionRepo::re_split);
+        let mut signatures = StdFuncSignatures::new();
+        signatures.insert("re_match".to_string(), "re_match(pattern, str)".to_string());
+        signatures.insert("re_find_all".to_string(), "re_find_all(pattern, str)".to_string());
+        signatures.insert("re_replace".to_string(), "re_replace(pattern, str, replacement)".to_string());
+        signatures.insert("re_split".to_string(), "re_split(pattern, str)".to_string());
+        Self {
+            funcs,
+            signatures
+        }
+    }
+
+    pub fn get_functions(&self) -> StdRegexFuncMap {
+        self.funcs.clone()
+    }
+
+    pub fn get_signatures(&self) -> StdFuncSignatures {
+        self.signatures.clone()
+    }
+
+    /// string arguments of the function: the pattern, the text and other ones
+    fn get_strs(args: &StdFuncArgs, argc: usize) -> Result<Vec<&str>, String> {
+        if args.len() != argc {
+            return Err(format!("expected {} arguments, find {}", argc, args.len()));
+        }
+        args.iter()
+            .map(|arg| match arg {
+                ValueVariant::String(s) => Ok(&**s),
+                v => Err(format!("expected string, find {}", v))
+            })
+            .collect()
+    }
+
+    /// map of named groups, if the pattern has names, otherwise list of the match and its groups,
+    /// groups, that didn't participate in the match, are nil
+    fn captures_value(regex: &Regex, captures: &Captures) -> ValueVariant {
+        let group = |m: Option<regex::Match>| m.map(|m| ValueVariant::String(m.as_str().into())).unwrap_or(ValueVariant::Nil);
+        if regex.capture_names().any(|name| name.is_some()) {
+            let groups: BTreeMap<String, ValueVariant> = regex.capture_names().flatten()
+                .map(|name| (name.to_string(), group(captures.name(name))))
+                .collect();
+            ValueVariant::Map(Rc::new(groups))
+        } else {
+            ValueVariant::List(Rc::new(captures.iter().map(group).collect()))
+        }
+    }
+
+    /// the first match in the string or nil
+    fn re_match(cache: &mut RegexCache, args: &StdFuncArgs) -> StdFuncResult {
+        let strs = Self::get_strs(args, 2)?;
+        let (pattern, text) = (strs[0], strs[1]);
+        let regex = cache.get(pattern)?;
+        Ok(Some(regex.captures(text).map(|captures| Self::captures_value(regex, &captures)).unwrap_or(ValueVariant::Nil)))
+    }
+
+    /// strings of all matches, or values of their groups like in re_match, if the pattern has groups
+    fn re_find_all(cache: &mut RegexCache, args: &StdFuncArgs) -> StdFuncResult {
+        let strs = Self::get_strs(args, 2)?;
+        let (pattern, text) = (strs[0], strs[1]);
+        let regex = cache.get(pattern)?;
+        let matches = if regex.captures_len() == 1 {
+            regex.find_iter(text).map(|m| ValueVariant::String(m.as_str().into())).collect()
+        } else {
+            regex.captures_iter(text).map(|captures| Self::captures_value(regex, &captures)).collect()
+        };
+        Ok(Some(ValueVariant::List(Rc::new(matches))))
+    }
+
+    /// replaces all matches, $1 or ${name} in the replacement are groups of the match
+    fn re_replace(cache: &mut RegexCache, args: &StdFuncArgs) -> StdFuncResult {
+        let strs = Self::get_strs(args, 3)?;
+        let (pattern, text, replacement) = (strs[0], strs[1], strs[2]);
+        let regex = cache.get(pattern)?;
+        Ok(Some(ValueVariant::String(regex.replace_all(text, replacement).into())))
+    }
+
+    fn re_split(cache: &mut RegexCache, args: &StdFuncArgs) -> StdFuncResult {
+        let strs = Self::get_strs(args, 2)?;
+        let (pattern, text) = (strs[0], strs[1]);
+        let regex = cache.get(pattern)?;
+        Ok(Some(ValueVariant::List(Rc::new(regex.split(text).map(|part| ValueVariant::String(part.into())).collect()))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn regex_cache_test() {
+        let mut cache = RegexCache::default();
+        let args = vec![ValueVariant::String("\\d+".into()), ValueVariant::String("a1 b22".into())];
+        for _ in 0..3 {
+            assert_eq!(RegexFunctionRepo::re_find_all(&mut cache, &args).unwrap().unwrap().to_string(), "[\"1\", \"22\"]");
+        }
+        // the pattern is compiled once and reused by the next calls
+        assert_eq!(cache.regexes.len(), 1);
+        assert!(cache.get("(").is_err());
+        assert_eq!(cache.regexes.len(), 1);
+        for i in 0..REGEX_CACHE_SIZE {
+            cache.get(&format!("x{}", i)).unwrap();
+        }
+        // the full cache is cleared before compiling a new pattern
+        assert_eq!(cache.regexes.len(), 1);
+        assert!(cache.regexes.contains_key(&format!("x{}", REGEX_CACHE_SIZE - 1)));
+    }
+}
@@ -1,10 +1,14 @@
+use std::rc::Rc;
+
 use crate::ex_core::ValueVariant;
 
-use super::method_repository::{StdMethodsMap, StdMethodsRepository, StdMethodArgs, StdMethodResult};
+use super::ex_bytes::{decode_binary, encode_text, get_encoding};
+use super::method_repository::{StdMethodsMap, StdMutMethodsMap, StdMethodsRepository, StdMethodArgs, StdMethodResult};
 
 
 pub struct StringMethods {
-    methods: StdMethodsMap
+    methods: StdMethodsMap,
+    mut_methods: StdMutMethodsMap,
 }
 
 impl StringMethods {
@@ -12,15 +16,21 @@ impl StringMethods {
         let mut methods = StdMethodsMap::new();
         methods.insert("to_int".to_string(), StringMethods::to_int);
         methods.insert("to_float".to_string(), StringMethods::to_float);
+        methods.insert("chars".to_string(), StringMethods::chars);
+        methods.insert("encode".to_string(), StringMethods::encode);
+        methods.insert("decode".to_string(), StringMethods::decode);
+        let mut mut_methods = StdMutMethodsMap::new();
+        mut_methods.insert("push_str".to_string(), StringMethods::push_str);
         return Self {
-            methods : methods
+            methods : methods,
+            mut_methods,
         }
     }
     
     fn get_str(v: &ValueVariant) -> Result<String, String> {
         match v {
             ValueVariant::String(s) => {
-                return Ok(s.clone());
+                return Ok(s.to_string());
             }
             _ => {
                 return Err(format!("exptected int, find {}", v.to_string()));
@@ -52,14 +62,51 @@ impl StringMethods {
         }
     }
 
+    fn chars(this: &ValueVariant, args:&StdMethodArgs) -> StdMethodResult {
+        if !args.is_empty() {
+            return Err(String::from("method arg expected 0 arguments"));
+        }
+        let this_s = Self::get_str(this)?;
+        Ok(Some(ValueVariant::List(Rc::new(this_s.chars().map(ValueVariant::Char).collect()))))
+    }
+
+    /// encode("utf8") or encode("latin1") returns bytes of the string
+    fn encode(this: &ValueVariant, args:&StdMethodArgs) -> StdMethodResult {
+        let encoding = get_encoding(args)?;
+        let bytes = encode_text(&Self::get_str(this)?, encoding)?;
+        Ok(Some(ValueVariant::Bytes(Rc::new(bytes))))
+    }
+
+    /// decode("base64") or decode("hex") returns bytes, that the string represents
+    fn decode(this: &ValueVariant, args:&StdMethodArgs) -> StdMethodResult {
+        let encoding = get_encoding(args)?;
+        let bytes = decode_binary(&Self::get_str(this)?, encoding)?;
+        Ok(Some(ValueVariant::Bytes(Rc::new(bytes))))
+    }
+
+    fn push_str(this: &mut ValueVariant, args:&StdMethodArgs) -> StdMethodResult {
+        if args.len() != 1 {
+            return Err(format!("method arg expected 1 argument, find {}", args.len()));
+        }
+        let (ValueVariant::String(this_s), ValueVariant::String(s)) = (&*this, &args[0]) else {
+            return Err(format!("exptected string argument, find {}", args[0]));
+        };
+        *this = ValueVariant::String([&**this_s, &**s].concat().into());
+        Ok(None)
+    }
+
 }
 
 impl StdMethodsRepository for StringMethods {
-    fn get_diterminant(&self) -> std::mem::Discriminant<crate::ex_core::ValueVariant> {
-        return std::mem::discriminant(&&crate::ex_core::ValueVariant::String("".to_string()));
+    fn get_type_name(&self) -> &'static str {
+        return "string";
     }
 
     fn get_methods(&self) -> StdMethodsMap {
         return self.methods.clone();
     }
+
+    fn get_mut_methods(&self) -> StdMutMethodsMap {
+        self.mut_methods.clone()
+    }
 }
@@ -6,7 +6,10 @@ pub type StdFuncArgs = Vec<ValueVariant>;
 pub type StdFuncResult = Result<Option<ValueVariant>, String>;
 pub type StdFunc = fn(&StdFuncArgs) -> StdFuncResult;
 pub type StdFuncMap = HashMap<String, StdFunc>;
+/// human readable signatures of functions by function name, like "env(name, [default])"
+pub type StdFuncSignatures = HashMap<String, String>;
 
 pub trait FunctionRepository {
     fn get_functions(&self) -> StdFuncMap;
+    fn get_signatures(&self) -> StdFuncSignatures;
 }
\ No newline at end of file
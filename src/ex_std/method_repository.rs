@@ -1,11 +1,19 @@
-use std::{collections::HashMap, mem::Discriminant};
+use std::collections::HashMap;
 use crate::ex_core::interp::ValueVariant;
 pub type StdMethodArgs = Vec<ValueVariant>;
 pub type StdMethodResult = Result<Option<ValueVariant>, String>;
 pub type StdMethod = fn(&ValueVariant, &StdMethodArgs) -> StdMethodResult;
 pub type StdMethodsMap = HashMap<String, StdMethod>;
+/// method, that changes the value it's called on
+pub type StdMutMethod = fn(&mut ValueVariant, &StdMethodArgs) -> StdMethodResult;
+pub type StdMutMethodsMap = HashMap<String, StdMutMethod>;
 
 pub trait StdMethodsRepository {
-    fn get_diterminant(&self) -> Discriminant<ValueVariant>;
+    /// name of the value type the methods are called on, see ValueVariant::type_name
+    fn get_type_name(&self) -> &'static str;
     fn get_methods(&self) -> StdMethodsMap;
+    /// methods, that change the value, they are called only on variables
+    fn get_mut_methods(&self) -> StdMutMethodsMap {
+        StdMutMethodsMap::new()
+    }
 }
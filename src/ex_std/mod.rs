@@ -1,13 +1,33 @@
+
 mod func_respository;
 mod ex_io;
+mod ex_env;
+mod ex_process;
+mod ex_expand;
 mod ex_int;
 mod ex_str; 
+mod ex_list;
+mod ex_map;
+mod ex_bytes;
+mod ex_char;
+mod ex_regex;
+mod ex_json;
 mod method_repository;
 
 pub use func_respository::FunctionRepository;
 pub use ex_io::IOFunctionRepo;
+pub use ex_env::EnvFunctionRepo;
+pub use ex_expand::ExpandFunctionRepo;
+pub use ex_json::JsonFunctionRepo;
+pub use ex_process::{ProcessFunctionRepo, ProcessMethods, ProcessRef};
 pub use func_respository::StdFuncMap;
-pub use method_repository::StdMethodsMap;
+pub use func_respository::StdFuncSignatures;
+pub use method_repository::{StdMethodsMap, StdMutMethod, StdMutMethodsMap};
 pub use method_repository::StdMethodsRepository;
 pub use ex_int::IntMethods;
-pub use ex_str::StringMethods;
\ No newline at end of file
+pub use ex_str::StringMethods;
+pub use ex_list::ListMethods;
+pub use ex_map::MapMethods;
+pub use ex_bytes::BytesMethods;
+pub use ex_char::CharMethods;
+pub use ex_regex::{RegexCache, RegexFunctionRepo, StdRegexFuncMap};
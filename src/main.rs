@@ -1,50 +1,28 @@
 use std::env;
-use std::io::{self, Write};
 use std::fs;
-use crate::ex_core::{tokenize, Interpreter, Parser};
+use std::path::Path;
+use std::process::ExitCode;
+use std::rc::Rc;
+use crate::ex_core::{tokenize, Interpreter, Parser, ValueVariant};
 
 mod ex_std;
 mod ex_core;
+mod ex_repl;
 
-fn run_cl_interp() {
-    println!("Run commnand line Ex interpreter");
-    let mut interp = Interpreter::new();
-    loop {
-        print!(">>> ");
-        io::stdout().flush().expect("Failed to flush stdout");
-        let mut input = String::new();
-        if let Ok(_) = io::stdin().read_line(&mut input) {
-            let tokens = tokenize(&input);
-            let mut parser = Parser::new(&tokens);
-            let parse_result = parser.parse();
-            if let Ok(expr) = parse_result {
-                if let Err(err_msg) = interp.interp_expr(expr) {
-                    println!("Interpreter error: {}", err_msg);
-                }
-            } else if let Err(err_msg) = parse_result {
-                println!("Parse error: {}", err_msg);
-            }
-        } else {
-            println!("can't parse input");
-        }
-    }
-}
-
-fn interp_file(path: &String) -> io::Result<()> {
-    let file_content = fs::read_to_string(path)?;
-    let tokens = tokenize(&file_content);
+/// interprets the script at 'path', script arguments are available in the script as 'args' list
+fn interp_file(path: &String, script_args: &[String]) -> Result<(), String> {
+    let file_content = fs::read_to_string(path).map_err(|err| format!("Can't read file {}: {}", path, err))?;
+    let tokens = tokenize(&file_content).map_err(|err| format!("Lexer error: {}", err))?;
     let mut parser = Parser::new(&tokens);
-    let parse_res = parser.parse();
-    if let Ok(expr) = parse_res {
-        let mut interp = Interpreter::new();
-        let interp_res = interp.interp_expr(expr);
-        if let Err(err_msg) = interp_res {
-            println!("Interpreter error: {}", err_msg);
-        }
-    } else if let Err(err_msg) = parse_res {
-        println!("Parsing error: {}", err_msg);
+    let expr = parser.parse().map_err(|err_msg| format!("Parsing error: {}", err_msg))?;
+    let mut interp = Interpreter::new();
+    interp.set_script_path(Path::new(path));
+    let args_value = ValueVariant::List(Rc::new(script_args.iter().map(|arg| ValueVariant::String(arg.as_str().into())).collect()));
+    interp.add_var("args", &args_value);
+    for warning in interp.check(expr.as_ref()) {
+        eprintln!("Warning: {}", warning);
     }
-    Ok(())
+    interp.interp_expr(expr).map_err(|err_msg| format!("Interpreter error: {}", err_msg))
 }
 
 fn print_usage() {
@@ -52,22 +30,23 @@ fn print_usage() {
     println!("To run the command line interpreter: ");
     println!("    ex.exe");
     println!("To interpret a file: ");
-    println!("    ex.exe <path_to_file>");
+    println!("    ex.exe <path_to_file> [script args...]");
     println!("To show this message: ");
     println!("    ex.exe --help or ex.exe -h");
 }
 
-fn main() -> io::Result<()>{
+fn main() -> ExitCode {
     let args: Vec<String> = env::args().collect();
-    if args.contains(&String::from("--help")) || args.contains(&String::from("-h")) {
-        print_usage();
-    } else if args.len() == 1 {
-        run_cl_interp();
-    } else if args.len() == 2 {
-        interp_file(&args[1])?;
-    } else {
-        println!("Error: not valid count of args");
+    if args.len() == 1 {
+        if let Err(err) = ex_repl::run_repl() {
+            eprintln!("Command line interpreter error: {}", err);
+            return ExitCode::FAILURE;
+        }
+    } else if args[1] == "--help" || args[1] == "-h" {
         print_usage();
+    } else if let Err(err_msg) = interp_file(&args[1], &args[2..]) {
+        eprintln!("{}", err_msg);
+        return ExitCode::FAILURE;
     }
-    Ok(())    
+    ExitCode::SUCCESS
 }